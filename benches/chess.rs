@@ -0,0 +1,95 @@
+// Run with "cargo bench", the move generation and perft benchmarks report their throughput in nodes per second
+// Compare against a baseline with "cargo bench -- --save-baseline main" and "cargo bench -- --baseline main"
+use std::hint::black_box;
+
+use criterion::{ criterion_group, criterion_main, BenchmarkId, Criterion, Throughput };
+use puhl_chess::*;
+
+// https://www.chessprogramming.org/Perft_Results
+const POSITIONS: [(&str, &str); 6] = [
+    ("initial", "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+    ("kiwipete", "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"),
+    ("position_3", "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1"),
+    ("position_4", "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1"),
+    ("position_5", "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8"),
+    ("position_6", "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10"),
+];
+
+fn bench_move_generation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("legal_moves");
+    for (name, fen) in POSITIONS {
+        let chess_board = ChessBoard::new(Some(fen)).unwrap();
+        group.throughput(Throughput::Elements(chess_board.legal_moves().len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &chess_board, |b, chess_board| {
+            b.iter(|| black_box(chess_board).legal_moves())
+        });
+    }
+    group.finish();
+}
+
+fn bench_make_move(c: &mut Criterion) {
+    let mut group = c.benchmark_group("make_move");
+    for (name, fen) in POSITIONS {
+        let chess_board = ChessBoard::new(Some(fen)).unwrap();
+        let moves = chess_board.legal_moves();
+        group.throughput(Throughput::Elements(moves.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &chess_board, |b, chess_board| {
+            b.iter(|| {
+                for chess_move in &moves {
+                    black_box(chess_board.make_move(*chess_move));
+                }
+            })
+        });
+    }
+
+    // Every promotion of b2a1 and b2b1, which also includes the promotion resolution
+    let chess_board = ChessBoard::new(Some("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 b kq - 0 1")).unwrap();
+    let promotions: Vec<ChessMove> = chess_board.legal_moves().into_iter().filter(|chess_move| chess_move.promotion.is_some()).collect();
+    group.throughput(Throughput::Elements(promotions.len() as u64));
+    group.bench_function("promotions", |b| {
+        b.iter(|| {
+            for chess_move in &promotions {
+                black_box(chess_board.make_move(*chess_move));
+            }
+        })
+    });
+    group.finish();
+}
+
+fn bench_info(c: &mut Criterion) {
+    let mut group = c.benchmark_group("info");
+    for (name, fen) in POSITIONS {
+        let chess_board = ChessBoard::new(Some(fen)).unwrap();
+        group.bench_with_input(BenchmarkId::from_parameter(name), &chess_board, |b, chess_board| {
+            b.iter(|| black_box(chess_board).info())
+        });
+    }
+    group.finish();
+}
+
+fn bench_fen(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fen_parse");
+    for (name, fen) in POSITIONS {
+        group.bench_with_input(BenchmarkId::from_parameter(name), fen, |b, fen| {
+            b.iter(|| ChessBoard::new(Some(black_box(fen))))
+        });
+    }
+    group.finish();
+}
+
+fn bench_perft(c: &mut Criterion) {
+    let mut group = c.benchmark_group("perft");
+    group.sample_size(10);
+    for (name, fen, depth) in [ ("initial", POSITIONS[0].1, 5), ("kiwipete", POSITIONS[1].1, 4), ("position_3", POSITIONS[2].1, 5),
+                                ("position_4", POSITIONS[3].1, 4), ("position_5", POSITIONS[4].1, 4), ("position_6", POSITIONS[5].1, 4) ] {
+        let chess_board = ChessBoard::new(Some(fen)).unwrap();
+        group.throughput(Throughput::Elements(perft(&chess_board, depth)));
+        group.bench_with_input(BenchmarkId::new(name, depth), &chess_board, |b, chess_board| {
+            b.iter(|| perft(black_box(chess_board), depth))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_move_generation, bench_make_move, bench_info, bench_fen, bench_perft);
+criterion_main!(benches);
@@ -0,0 +1,82 @@
+//! Counts the leaf nodes of the legal move tree, printing the count below every root move
+//! The output has the same format as the "go perft" command of Stockfish, so it can be diffed against a reference engine
+
+use std::env;
+use std::process;
+use std::time::Instant;
+
+use puhl_chess::*;
+
+const USAGE: &str = "Usage: perft <depth> [fen] [--moves <uci moves>...] [--hash <MB>] [--threads <N>]";
+
+struct Args {
+    depth: usize,
+    fen: Option<String>,
+    moves: Vec<String>,
+    options: PerftOptions,
+}
+
+fn parse_args() -> Option<Args> {
+    let mut args = env::args().skip(1);
+    let depth = args.next()?.parse().ok()?;
+    let mut parsed = Args { depth, fen: None, moves: vec![], options: PerftOptions::default() };
+
+    let mut reading_moves = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--moves" => reading_moves = true,
+            "--hash" => { parsed.options.hash_size_mb = args.next()?.parse().ok()?; reading_moves = false; }
+            "--threads" => { parsed.options.threads = args.next()?.parse().ok()?; reading_moves = false; }
+            _ if reading_moves => parsed.moves.extend(arg.split_whitespace().map(String::from)),
+            _ if parsed.fen.is_none() => parsed.fen = Some(arg),
+            _ => return None,
+        }
+    }
+    Some(parsed)
+}
+
+fn main() {
+    let args = match parse_args() {
+        Some(args) => args,
+        None => {
+            eprintln!("{}", USAGE);
+            process::exit(1);
+        }
+    };
+
+    let mut chess_board = match ChessBoard::new(args.fen.as_deref()) {
+        Some(chess_board) => chess_board,
+        None => {
+            eprintln!("Invalid FEN string");
+            process::exit(1);
+        }
+    };
+
+    for uci in &args.moves {
+        chess_board = match chess_board.parse_move(uci).and_then(|chess_move| chess_board.make_move(chess_move)) {
+            Some((chess_board, _)) => chess_board,
+            None => {
+                eprintln!("Illegal move: {}", uci);
+                process::exit(1);
+            }
+        };
+    }
+
+    let start = Instant::now();
+    let mut divide: Vec<(String, u64)> = perft_divide_with_options(&chess_board, args.depth, &args.options)
+        .into_iter()
+        .map(|(chess_move, count)| (chess_move.to_string(), count))
+        .collect();
+    let elapsed = start.elapsed();
+
+    // Sorted so the output can be diffed line by line
+    divide.sort();
+    for (chess_move, count) in &divide {
+        println!("{}: {}", chess_move, count);
+    }
+    let nodes: u64 = if args.depth == 0 { 1 } else { divide.iter().map(|(_, count)| count).sum() };
+    println!();
+    println!("Nodes searched: {}", nodes);
+
+    eprintln!("Time: {:.3}s ({:.0} nodes/s)", elapsed.as_secs_f64(), nodes as f64 / elapsed.as_secs_f64().max(1e-9));
+}
@@ -0,0 +1,257 @@
+//! Plays chess with the Universal Chess Interface protocol over stdin and stdout, so chess GUIs and tournament managers can use the search
+//! https://www.chessprogramming.org/UCI
+
+use std::io::{ self, BufRead };
+use std::sync::Arc;
+use std::sync::atomic::{ AtomicBool, AtomicU64, Ordering };
+use std::thread::{ self, JoinHandle };
+use std::time::Duration;
+
+use puhl_chess::*;
+
+const MAX_HASH_SIZE_MB: usize = 65536;
+const MAX_THREADS: usize = 256;
+const MAX_MULTI_PV: usize = 256;
+
+// The parameters of the go command
+#[derive(Debug, Default)]
+struct Go {
+    time: [Option<Duration>; 2],
+    increment: [Option<Duration>; 2],
+    moves_to_go: Option<u32>,
+    depth: Option<u32>,
+    nodes: Option<u64>,
+    move_time: Option<Duration>,
+    mate: Option<i32>,
+    infinite: bool,
+    ponder: bool,
+}
+
+impl Go {
+    fn parse(tokens: &[&str]) -> Go {
+        let mut go = Go::default();
+        let mut tokens = tokens.iter();
+        let millis = |value: Option<&&str>| value.and_then(|value| value.parse::<i64>().ok()).map(|millis| Duration::from_millis(millis.max(0) as u64));
+        while let Some(token) = tokens.next() {
+            match *token {
+                "wtime" => go.time[PieceColor::White as usize] = millis(tokens.next()),
+                "btime" => go.time[PieceColor::Black as usize] = millis(tokens.next()),
+                "winc" => go.increment[PieceColor::White as usize] = millis(tokens.next()),
+                "binc" => go.increment[PieceColor::Black as usize] = millis(tokens.next()),
+                "movestogo" => go.moves_to_go = tokens.next().and_then(|value| value.parse().ok()),
+                "depth" => go.depth = tokens.next().and_then(|value| value.parse().ok()),
+                "nodes" => go.nodes = tokens.next().and_then(|value| value.parse().ok()),
+                "movetime" => go.move_time = millis(tokens.next()),
+                "mate" => go.mate = tokens.next().and_then(|value| value.parse().ok()),
+                "infinite" => go.infinite = true,
+                "ponder" => go.ponder = true,
+                _ => {}
+            }
+        }
+        go
+    }
+
+    // The time to spend on the move of "color", None if the search isn't limited by time
+    fn time_limit(&self, color: PieceColor) -> Option<Duration> {
+        if self.move_time.is_some() {
+            return self.move_time;
+        }
+        let time = self.time[color as usize]?;
+        Some(time_budget(time, self.increment[color as usize].unwrap_or_default(), self.moves_to_go))
+    }
+}
+
+struct Engine {
+    // None while the search thread owns the searcher
+    searcher: Option<Searcher>,
+    search_thread: Option<JoinHandle<Searcher>>,
+    stop: Arc<AtomicBool>,
+    // Set while the engine searches on the time of the opponent, the best move is only printed after ponderhit or stop
+    pondering: Arc<AtomicBool>,
+    // Counts the go commands, so that a timer only stops the search it was started for
+    generation: Arc<AtomicU64>,
+    // The time of the move searched by pondering, started by ponderhit
+    ponder_time: Option<Duration>,
+    game: Game,
+}
+
+impl Engine {
+    fn new() -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        Engine {
+            searcher: Some(Searcher::new(stop.clone())),
+            search_thread: None,
+            stop,
+            pondering: Arc::new(AtomicBool::new(false)),
+            generation: Arc::new(AtomicU64::new(0)),
+            ponder_time: None,
+            game: Game::new(None).unwrap(),
+        }
+    }
+
+    // Returns false when the engine should quit
+    fn handle(&mut self, line: &str) -> bool {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let Some((command, arguments)) = tokens.split_first() else {
+            return true;
+        };
+        match *command {
+            "uci" => {
+                println!("id name {} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+                println!("id author INDA25PlusPlus");
+                println!("option name Hash type spin default {} min 1 max {}", DEFAULT_HASH_SIZE_MB, MAX_HASH_SIZE_MB);
+                println!("option name Threads type spin default 1 min 1 max {}", MAX_THREADS);
+                println!("option name MultiPV type spin default 1 min 1 max {}", MAX_MULTI_PV);
+                println!("uciok");
+            }
+            "isready" => println!("readyok"),
+            "ucinewgame" => {
+                self.searcher().transposition_table().clear();
+                self.game = Game::new(None).unwrap();
+            }
+            "position" => match parse_position(arguments) {
+                Some(game) => self.game = game,
+                None => println!("info string Invalid position: {}", arguments.join(" ")),
+            },
+            "setoption" => self.set_option(arguments),
+            "go" => self.go(Go::parse(arguments)),
+            "stop" => self.stop(),
+            "ponderhit" => self.ponder_hit(),
+            "quit" => {
+                self.stop();
+                return false;
+            }
+            "debug" | "register" => {}
+            _ => println!("info string Unknown command: {}", line.trim()),
+        }
+        true
+    }
+
+    // Stops the search and waits for it to print the best move
+    fn stop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        self.pondering.store(false, Ordering::Relaxed);
+        if let Some(search_thread) = self.search_thread.take() {
+            self.searcher = Some(search_thread.join().unwrap());
+        }
+    }
+
+    fn searcher(&mut self) -> &mut Searcher {
+        self.stop();
+        self.searcher.as_mut().unwrap()
+    }
+
+    // Parses "name <name> value <value>", the name may contain spaces
+    fn set_option(&mut self, arguments: &[&str]) {
+        let value_index = arguments.iter().position(|token| *token == "value").unwrap_or(arguments.len());
+        let name = arguments.get(1..value_index).unwrap_or_default().join(" ");
+        let value = arguments.get(value_index + 1..).unwrap_or_default().join(" ");
+        let Ok(value) = value.parse::<usize>() else {
+            println!("info string Invalid value for option {}: {}", name, value);
+            return;
+        };
+        match name.to_lowercase().as_str() {
+            "hash" => self.searcher().transposition_table_mut().resize(value.clamp(1, MAX_HASH_SIZE_MB)),
+            "threads" => self.searcher().set_threads(value.clamp(1, MAX_THREADS)),
+            "multipv" => self.searcher().set_multi_pv(value.clamp(1, MAX_MULTI_PV)),
+            _ => println!("info string Unknown option: {}", name),
+        }
+    }
+
+    fn go(&mut self, go: Go) {
+        self.stop();
+        self.stop.store(false, Ordering::Relaxed);
+        self.pondering.store(go.ponder, Ordering::Relaxed);
+        self.generation.fetch_add(1, Ordering::Relaxed);
+
+        let chess_board = self.game.chess_board().clone();
+        let history = self.game.history();
+        let time = go.time_limit(chess_board.inner.current_color);
+        // A search on the time of the opponent only gets its time limit once the opponent plays the expected move
+        self.ponder_time = if go.ponder { time } else { None };
+        let limits = SearchLimits {
+            depth: go.depth,
+            nodes: go.nodes,
+            time: if go.ponder || go.infinite { None } else { time },
+        };
+
+        let mut searcher = self.searcher.take().unwrap();
+        let stop = self.stop.clone();
+        let pondering = self.pondering.clone();
+        self.search_thread = Some(thread::spawn(move || {
+            let result = searcher.search(&chess_board, &history, limits, |info| {
+                print_info(info);
+                // A mate within the moves of "go mate" ends the search
+                if let (Some(mate), Score::Mate(moves)) = (go.mate, info.score) && info.multi_pv == 1 && moves > 0 && moves <= mate {
+                    stop.store(true, Ordering::Relaxed);
+                }
+            });
+            // The best move may only be printed after stop, or after ponderhit when pondering
+            while !stop.load(Ordering::Relaxed) && (go.infinite || pondering.load(Ordering::Relaxed)) {
+                thread::sleep(Duration::from_millis(1));
+            }
+            print_best_move(&result);
+            searcher
+        }));
+    }
+
+    // The opponent played the move the engine pondered on, the search continues on the own time
+    fn ponder_hit(&mut self) {
+        self.pondering.store(false, Ordering::Relaxed);
+        if let Some(time) = self.ponder_time.take() {
+            let stop = self.stop.clone();
+            let generation = self.generation.clone();
+            let current = generation.load(Ordering::Relaxed);
+            thread::spawn(move || {
+                thread::sleep(time);
+                if generation.load(Ordering::Relaxed) == current {
+                    stop.store(true, Ordering::Relaxed);
+                }
+            });
+        }
+    }
+}
+
+// Parses "startpos" or "fen <fen>", followed by "moves" and the moves played from the position
+fn parse_position(arguments: &[&str]) -> Option<Game> {
+    let moves_index = arguments.iter().position(|token| *token == "moves").unwrap_or(arguments.len());
+    let mut game = match *arguments.first()? {
+        "startpos" => Game::new(None)?,
+        "fen" => Game::new(Some(&arguments[1..moves_index].join(" ")))?,
+        _ => return None,
+    };
+    for uci in arguments.get(moves_index + 1..).unwrap_or_default() {
+        let chess_move = game.chess_board().parse_move(uci)?;
+        game.make_move(chess_move)?;
+    }
+    Some(game)
+}
+
+fn print_info(info: &SearchInfo) {
+    let pv: Vec<String> = info.pv.iter().map(|chess_move| chess_move.to_string()).collect();
+    println!("info depth {} multipv {} score {} nodes {} nps {} time {} pv {}",
+        info.depth, info.multi_pv, info.score, info.nodes, info.nps, info.time.as_millis(), pv.join(" "));
+}
+
+// The move the engine expects as the reply is suggested for pondering
+fn print_best_move(result: &SearchResult) {
+    match (result.best_move, result.pv.get(1)) {
+        (Some(best_move), Some(ponder)) if result.pv[0] == best_move => println!("bestmove {} ponder {}", best_move, ponder),
+        (Some(best_move), _) => println!("bestmove {}", best_move),
+        // UCI writes the null move as 0000, sent when there is no legal move
+        (None, _) => println!("bestmove 0000"),
+    }
+}
+
+fn main() {
+    let mut engine = Engine::new();
+    for line in io::stdin().lock().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if !engine.handle(&line) {
+            return;
+        }
+    }
+    engine.stop();
+}
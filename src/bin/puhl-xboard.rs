@@ -0,0 +1,314 @@
+//! Plays chess with the Chess Engine Communication Protocol version 2 of XBoard and WinBoard over stdin and stdout
+//! The moves of the game are kept in a Game, so undo, repetitions and the end of the game follow its history
+//! https://www.gnu.org/software/xboard/engine-intf.html
+
+use std::io::{ self, BufRead };
+use std::sync::Arc;
+use std::sync::atomic::{ AtomicBool, Ordering };
+use std::sync::mpsc::{ self, Sender };
+use std::thread::{ self, JoinHandle };
+use std::time::Duration;
+
+use puhl_chess::*;
+
+// Mate in N moves is written as 100000 + N, getting mated in N moves as -100000 - N
+const XBOARD_MATE_SCORE: i32 = 100000;
+
+enum Event {
+    Command(String),
+    // The search with the number has finished
+    SearchDone(u64),
+}
+
+// The time control of the level and st commands
+#[derive(Debug, Clone, Copy)]
+enum TimeControl {
+    // Moves per session, 0 for the whole game, the base time and the increment
+    Level { moves: u32, base: Duration, increment: Duration },
+    // Exactly this time per move
+    PerMove(Duration),
+}
+
+struct Engine {
+    // None while the search thread owns the searcher
+    searcher: Option<Searcher>,
+    search_thread: Option<JoinHandle<(Searcher, SearchResult)>>,
+    stop: Arc<AtomicBool>,
+    events: Sender<Event>,
+    // Counts the searches, so that a search which has been cancelled is never played
+    search_number: u64,
+    game: Game,
+    // The color the engine plays, None in force mode
+    engine_color: Option<PieceColor>,
+    time_control: TimeControl,
+    depth: Option<u32>,
+    // The clock of the engine set by the time command
+    engine_time: Option<Duration>,
+    post: bool,
+}
+
+impl Engine {
+    fn new(events: Sender<Event>) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        Engine {
+            searcher: Some(Searcher::new(stop.clone())),
+            search_thread: None,
+            stop,
+            events,
+            search_number: 0,
+            game: Game::new(None).unwrap(),
+            engine_color: Some(PieceColor::Black),
+            time_control: TimeControl::Level { moves: 40, base: Duration::from_secs(300), increment: Duration::ZERO },
+            depth: None,
+            engine_time: None,
+            post: false,
+        }
+    }
+
+    // Returns false when the engine should quit
+    fn handle(&mut self, line: &str) -> bool {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let Some((command, arguments)) = tokens.split_first() else {
+            return true;
+        };
+        match *command {
+            "xboard" | "accepted" | "rejected" | "random" | "hard" | "easy" | "computer" | "name" | "rating" | "draw" | "otim" => {}
+            "protover" => {
+                println!("feature myname=\"{} {}\" ping=1 setboard=1 usermove=1 playother=1 colors=0 sigint=0 sigterm=0 reuse=1 analyze=0 done=1",
+                    env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+            }
+            "new" => {
+                self.searcher().transposition_table().clear();
+                self.game = Game::new(None).unwrap();
+                self.engine_color = Some(PieceColor::Black);
+                self.depth = None;
+                self.engine_time = None;
+            }
+            "force" => {
+                self.cancel();
+                self.engine_color = None;
+            }
+            "go" => {
+                self.cancel();
+                self.engine_color = Some(self.game.chess_board().inner.current_color);
+                self.think();
+            }
+            "playother" => {
+                self.cancel();
+                self.engine_color = Some(PieceColor::opposite(self.game.chess_board().inner.current_color));
+            }
+            "usermove" => self.user_move(arguments.first().copied().unwrap_or_default()),
+            "setboard" => {
+                self.cancel();
+                match Game::new(Some(&arguments.join(" "))) {
+                    Some(game) => self.game = game,
+                    None => println!("tellusererror Illegal position"),
+                }
+            }
+            "level" => match parse_level(arguments) {
+                Some(time_control) => self.time_control = time_control,
+                None => println!("Error (invalid time control): {}", line.trim()),
+            },
+            "st" => match arguments.first().and_then(|seconds| seconds.parse::<f64>().ok()).and_then(|seconds| Duration::try_from_secs_f64(seconds).ok()) {
+                Some(time) => self.time_control = TimeControl::PerMove(time),
+                None => println!("Error (invalid time): {}", line.trim()),
+            },
+            "sd" => self.depth = arguments.first().and_then(|depth| depth.parse().ok()),
+            "time" => self.engine_time = arguments.first().and_then(|centiseconds| centiseconds.parse::<u64>().ok()).map(|centiseconds| Duration::from_millis(centiseconds * 10)),
+            "undo" => self.take_back(1),
+            "remove" => self.take_back(2),
+            "post" => self.post = true,
+            "nopost" => self.post = false,
+            "result" => {
+                self.cancel();
+                self.engine_color = None;
+            }
+            "ping" => println!("pong {}", arguments.first().copied().unwrap_or_default()),
+            // Move now
+            "?" => self.stop.store(true, Ordering::Relaxed),
+            "quit" => {
+                self.cancel();
+                return false;
+            }
+            // Interfaces which haven't accepted the usermove feature send the moves alone
+            _ if self.game.chess_board().parse_move(command).is_some() => self.user_move(command),
+            _ => println!("Error (unknown command): {}", line.trim()),
+        }
+        true
+    }
+
+    // Stops the search without playing its move
+    fn cancel(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(search_thread) = self.search_thread.take() {
+            self.searcher = Some(search_thread.join().unwrap().0);
+        }
+    }
+
+    fn searcher(&mut self) -> &mut Searcher {
+        self.cancel();
+        self.searcher.as_mut().unwrap()
+    }
+
+    fn user_move(&mut self, uci: &str) {
+        self.cancel();
+        let Some(chess_move) = self.game.chess_board().parse_move(uci) else {
+            println!("Illegal move: {}", uci);
+            return;
+        };
+        if self.game.make_move(chess_move).is_none() {
+            println!("Illegal move: {}", uci);
+            return;
+        }
+        if !self.report_result(false) {
+            self.think();
+        }
+    }
+
+    fn take_back(&mut self, plies: usize) {
+        self.cancel();
+        for _ in 0..plies {
+            self.game.undo();
+        }
+    }
+
+    // Starts searching if it is the turn of the engine
+    fn think(&mut self) {
+        let chess_board = self.game.chess_board().clone();
+        let color = chess_board.inner.current_color;
+        if self.engine_color != Some(color) || self.game.result().state != GameState::Playing {
+            return;
+        }
+
+        self.stop.store(false, Ordering::Relaxed);
+        self.search_number += 1;
+        let search_number = self.search_number;
+        let limits = SearchLimits { depth: self.depth, nodes: None, time: Some(self.time_limit()) };
+        let history = self.game.history();
+        let mut searcher = self.searcher.take().unwrap();
+        let events = self.events.clone();
+        let post = self.post;
+        self.search_thread = Some(thread::spawn(move || {
+            let result = searcher.search(&chess_board, &history, limits, |info| {
+                if post {
+                    print_thinking(info);
+                }
+            });
+            // The receiver only goes away when the engine quits
+            let _ = events.send(Event::SearchDone(search_number));
+            (searcher, result)
+        }));
+    }
+
+    // Plays the move of the search if it hasn't been cancelled
+    fn search_done(&mut self, search_number: u64) {
+        if search_number != self.search_number {
+            return;
+        }
+        let Some(search_thread) = self.search_thread.take() else {
+            return;
+        };
+        let (searcher, result) = search_thread.join().unwrap();
+        self.searcher = Some(searcher);
+        let Some(best_move) = result.best_move else {
+            return;
+        };
+        self.game.make_move(best_move).unwrap();
+        println!("move {}", best_move);
+        // A draw is only claimed when the engine doesn't expect to win, the interface may claim it for the user
+        let winning = match result.score {
+            Score::Centipawns(centipawns) => centipawns > 0,
+            Score::Mate(moves) => moves > 0,
+        };
+        self.report_result(!winning);
+    }
+
+    // Prints the result if the game has ended, or if "claim_draw" is set and a draw can be claimed, returns true if it has
+    fn report_result(&mut self, claim_draw: bool) -> bool {
+        if claim_draw {
+            self.game.claim_draw();
+        }
+        let result = self.game.result();
+        if result.state == GameState::Playing {
+            return false;
+        }
+        let comment = match result.termination {
+            Some(Termination::Checkmate) if result.state == GameState::Win(PieceColor::White) => "White mates",
+            Some(Termination::Checkmate) => "Black mates",
+            Some(Termination::Stalemate) => "Stalemate",
+            Some(Termination::ThreefoldRepetition | Termination::FivefoldRepetition) => "Draw by repetition",
+            Some(Termination::FiftyMoveRule | Termination::SeventyFiveMoveRule) => "Draw by fifty move rule",
+            Some(Termination::InsufficientMaterial) => "Draw by insufficient material",
+            _ => "Game over",
+        };
+        println!("{} {{{}}}", result, comment);
+        true
+    }
+
+    // The time to spend on the move, a fixed time per move is the whole time until the next time control
+    fn time_limit(&self) -> Duration {
+        let (moves, base, increment) = match self.time_control {
+            TimeControl::PerMove(time) => return time_budget(time, Duration::ZERO, Some(1)),
+            TimeControl::Level { moves, base, increment } => (moves, base, increment),
+        };
+        let time = self.engine_time.unwrap_or(base);
+        let moves_played = (self.game.ply() / 2) as u32;
+        let moves_to_go = (moves > 0).then(|| moves - moves_played % moves);
+        time_budget(time, increment, moves_to_go)
+    }
+}
+
+// Parses "<moves per session> <base> <increment>", the base is in minutes or "minutes:seconds" and the increment in seconds
+fn parse_level(arguments: &[&str]) -> Option<TimeControl> {
+    let [moves, base, increment] = arguments else {
+        return None;
+    };
+    let base = match base.split_once(':') {
+        Some((minutes, seconds)) => minutes.parse::<u64>().ok()? * 60 + seconds.parse::<u64>().ok()?,
+        None => base.parse::<u64>().ok()? * 60,
+    };
+    Some(TimeControl::Level {
+        moves: moves.parse().ok()?,
+        base: Duration::from_secs(base),
+        increment: Duration::try_from_secs_f64(increment.parse().ok()?).ok()?,
+    })
+}
+
+// Prints "<depth> <score> <time> <nodes> <pv>" with the score in centipawns and the time in centiseconds
+fn print_thinking(info: &SearchInfo) {
+    let score = match info.score {
+        Score::Centipawns(centipawns) => centipawns,
+        Score::Mate(moves) if moves > 0 => XBOARD_MATE_SCORE + moves,
+        Score::Mate(moves) => -XBOARD_MATE_SCORE + moves,
+    };
+    let pv: Vec<String> = info.pv.iter().map(|chess_move| chess_move.to_string()).collect();
+    println!("{} {} {} {} {}", info.depth, score, info.time.as_millis() / 10, info.nodes, pv.join(" "));
+}
+
+fn main() {
+    let (sender, receiver) = mpsc::channel();
+    let mut engine = Engine::new(sender.clone());
+    // The commands are read on their own thread, so they arrive while the engine thinks
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if sender.send(Event::Command(line)).is_err() {
+                break;
+            }
+        }
+        let _ = sender.send(Event::Command("quit".to_string()));
+    });
+
+    for event in receiver {
+        match event {
+            Event::Command(line) => {
+                if !engine.handle(&line) {
+                    return;
+                }
+            }
+            Event::SearchDone(search_number) => engine.search_done(search_number),
+        }
+    }
+}
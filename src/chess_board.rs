@@ -1,13 +1,23 @@
+use std::any::Any;
+
 use crate::types::*;
 use crate::square::*;
+use crate::mv::*;
+use crate::promotion::*;
+use crate::variant::*;
+use crate::core::board::*;
+use crate::core::move_generation::*;
+use crate::core::precompute_masks::*;
+use crate::core::see::*;
 use crate::core::chess_board as internal;
 
 pub use crate::core::board::{ BOARD_SIZE, BOARD_FILES, BOARD_RANKS };
-pub use crate::core::piece::{ PieceType, PieceColor };
+pub use crate::core::piece::{ PieceType, PieceColor, Pocket };
+pub use crate::core::see::SEE_VALUES;
 
 /// Represenets the state of the game
 /// Win contains the color the side that won, its implied the other side has lost
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum GameState {
     Win(PieceColor),
     Draw,
@@ -23,27 +33,39 @@ pub struct ChessBoardInfo {
 }
 
 /// Represents the state of the chess board
+/// Variant is the rules the position is played by, standard chess unless another variant is given
 #[derive(Clone, Debug)]
 pub struct ChessBoard {
     pub inner: internal::ChessBoard,
+    variant: &'static dyn Variant,
 }
 
 impl ChessBoard {
-    const INITIAL_POSITION_FEN: &'static str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    pub const INITIAL_POSITION_FEN: &'static str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
 
     /// Returns a new ChessBoard with the pieces positioned as described in the 'fen' string
     /// If the fen string is None, it will use the initial chess position
     /// Returns None if the given Fen string was invalid
     pub fn new(fen: Option<&str>) -> Option<Self> {
-        let inner = internal::ChessBoard::new(match fen {
-            None => ChessBoard::INITIAL_POSITION_FEN,
-            Some(fen) => fen
-        });
+        ChessBoard::with_variant(&Standard, fen)
+    }
 
-        match inner {
-            None => None,
-            Some(inner) => Some(Self{ inner }),
-        }
+    /// Returns a new ChessBoard played by the rules of "variant"
+    /// If the fen string is None, it will use the start position of the variant
+    /// Returns None if the given Fen string was invalid
+    pub fn with_variant(variant: &'static dyn Variant, fen: Option<&str>) -> Option<Self> {
+        let inner = internal::ChessBoard::new(fen.unwrap_or(variant.start_fen()));
+        inner.map(|inner| Self{ inner, variant })
+    }
+
+    /// Returns the variant the position is played by
+    pub fn variant(&self) -> &'static dyn Variant {
+        self.variant
+    }
+
+    // Standard chess is the only variant the internal move generation can be used for directly
+    pub(crate) fn is_standard(&self) -> bool {
+        (self.variant as &dyn Any).is::<Standard>()
     }
 
     /// Returns the square positioned at "rank" and "file" on the board
@@ -53,40 +75,306 @@ impl ChessBoard {
     }
 
     /// Returns some state info of the chess board
+    /// The game is over if the variant has ended it, or if the current player has no legal moves
     pub fn info(&self) -> ChessBoardInfo {
-        let mut count: usize = 0;
-        // Check how many moves player has, if zero then its either stalemate or checkamte
-        for rank in 0..BOARD_RANKS {
-            for file in 0..BOARD_FILES {
-                let rank = Rank::new(rank).unwrap();
-                let file = File::new(file).unwrap();
-                let moves = self.square(rank, file).get_moves();
-                let moves = match moves {
-                    None => continue,
-                    Some(m) => m,
-                };
-                
-                count += moves.len();
+        let is_current_player_in_check = self.variant.is_check(self);
+        let game_state = match self.variant.outcome(self) {
+            Some(game_state) => game_state,
+            // Zero moves is either checkmate or stalemate
+            None if self.variant.legal_moves(self).is_empty() => {
+                if is_current_player_in_check {
+                    GameState::Win(PieceColor::opposite(self.inner.current_color))
+                } else {
+                    GameState::Draw     // Stalemate
+                }
             }
+            None => GameState::Playing,
+        };
+        ChessBoardInfo {
+            player_turn: self.inner.current_color,
+            is_current_player_in_check,
+            game_state,
+        }
+    }
+
+    /// Returns all legal moves of the current player, following the rules of the variant
+    /// A promotion is returned once for every piece type the pawn can be promoted to
+    /// Returns an empty vector if the variant has ended the game
+    pub fn legal_moves(&self) -> Vec<ChessMove> {
+        if self.variant.outcome(self).is_some() {
+            return vec![];
         }
-        
-        let is_current_player_in_check = self.inner.is_current_player_in_check();
-        let game_state = if count == 0 {
-            if is_current_player_in_check {
-                GameState::Win(PieceColor::opposite(self.inner.current_color))
+        self.variant.legal_moves(self)
+    }
+
+    /// Returns all legal moves of the current player by the rules of standard chess, ignoring the variant
+    pub fn standard_legal_moves(&self) -> Vec<ChessMove> {
+        let mut moves: Vec<ChessMove> = vec![];
+        let masks = LegalMasks::new(&self.inner);
+        let mut pieces = self.inner.all_pieces[self.inner.current_color as usize];
+        while pieces != 0 {
+            let src = pop_lsb(&mut pieces);
+            let piece_type = self.inner.get_piece_type((1 as BitBoard) << src);
+            let bb_moves = get_move_generator(piece_type)(&self.inner, &masks, src);
+            self.push_moves(&mut moves, src, piece_type, bb_moves, &PROMOTION_PIECE_TYPES);
+        }
+        moves
+    }
+
+    /// Returns the moves of the current player without castling and without considering the safety of the king
+    /// Promotions are returned for every piece type the variant allows
+    /// Variants in which the king explodes or can be captured, such as Atomic and Antichess, find their legal moves among them
+    pub fn pseudo_legal_moves(&self) -> Vec<ChessMove> {
+        let mut moves: Vec<ChessMove> = vec![];
+        let mut pieces = self.inner.all_pieces[self.inner.current_color as usize];
+        while pieces != 0 {
+            let src = pop_lsb(&mut pieces);
+            let piece_type = self.inner.get_piece_type((1 as BitBoard) << src);
+            let bb_moves = get_pseudo_legal_moves(&self.inner, src);
+            self.push_moves(&mut moves, src, piece_type, bb_moves, self.variant.promotion_piece_types());
+        }
+        moves
+    }
+
+    // Adds a move from "src" to every square of "bb_moves", once for every promotion if the pawn reaches the last rank
+    fn push_moves(&self, moves: &mut Vec<ChessMove>, src: usize, piece_type: PieceType, mut bb_moves: BitBoard, promotions: &[PieceType]) {
+        let src = Index::new(src).unwrap();
+        while bb_moves != 0 {
+            let dst = self.to_public_dst(src, Index::new(pop_lsb(&mut bb_moves)).unwrap());
+            if piece_type == PieceType::Pawn && dst.as_bb() & PROMOTION_RANKS != 0 {
+                for promotion in promotions {
+                    moves.push(ChessMove::new(src, dst, Some(*promotion)));
+                }
             } else {
-                GameState::Draw     // Stalemate
+                moves.push(ChessMove::new(src, dst, None));
             }
+        }
+    }
+
+    /// Performs the move on a CLONE of the chess board, resolving the promotion if there is one
+    /// Returns None if the move is not legal or if the promotion does not match the move
+    pub fn make_move(&self, chess_move: ChessMove) -> Option<(ChessBoard, MoveType)> {
+        if self.variant.outcome(self).is_some() || !self.variant.is_legal(self, chess_move) {
+            return None;
+        }
+
+        if let Some(piece_type) = chess_move.drop {
+            let mut chess_board = self.clone();
+            chess_board.inner.drop_piece(piece_type, chess_move.dst.get());
+            chess_board.inner.toggle_current_color();
+            self.variant.after_move(self, &mut chess_board, chess_move);
+            return Some((chess_board, MoveType::Drop));
+        }
+
+        let (result, move_type) = Move { src: chess_move.src, dst: chess_move.dst, chess_board: self }.make_move();
+        match (result, chess_move.promotion) {
+            (MoveResult::ChessBoard(chess_board), None) => Some((chess_board, move_type)),
+            (MoveResult::PawnPromotionResolver(resolver), Some(promotion)) if self.variant.promotion_piece_types().contains(&promotion) => {
+                Some(resolver.resolve(promotion))
+            }
+            _ => None,
+        }
+    }
+
+    // Performs a move of standard chess which is known to be legal, without the checks of make_move
+    pub(crate) fn make_move_unchecked(&self, chess_move: ChessMove) -> ChessBoard {
+        let mut chess_board = self.clone();
+        chess_board.inner.make_move(chess_move.src.get(), self.to_internal_dst(chess_move.src, chess_move.dst).as_bb());
+        if let Some(promotion) = chess_move.promotion {
+            chess_board.inner.resolve_promotion(promotion);
+        }
+        chess_board.inner.toggle_current_color();
+        chess_board
+    }
+
+    /// Returns true if the source and destination of the move are a legal move by the rules of standard chess, ignoring the variant
+    /// The promotion is not checked
+    pub fn is_standard_legal(&self, chess_move: ChessMove) -> bool {
+        let bb_src = chess_move.src.as_bb();
+        let bb_dst = self.to_internal_dst(chess_move.src, chess_move.dst).as_bb();
+        if chess_move.drop.is_some() || !self.inner.has_square_movable_piece(bb_src) {
+            return false;
+        }
+
+        let piece_type = self.inner.get_piece_type(bb_src);
+        get_move_generator(piece_type)(&self.inner, &LegalMasks::new(&self.inner), chess_move.src.get()) & bb_dst != 0
+    }
+
+    /// Returns true if the move puts the opponent in check, the move is not checked for legality
+    pub fn gives_check(&self, chess_move: ChessMove) -> bool {
+        let mut inner = self.inner.clone();
+        if let Some(piece_type) = chess_move.drop {
+            inner.drop_piece(piece_type, chess_move.dst.get());
+        } else {
+            inner.make_move(chess_move.src.get(), self.to_internal_dst(chess_move.src, chess_move.dst).as_bb());
+        }
+        if let Some(promotion) = chess_move.promotion {
+            inner.resolve_promotion(promotion);
+        }
+        inner.toggle_current_color();
+        inner.is_current_player_in_check()
+    }
+
+    /// Returns the piece type the move captures, a pawn for en passant
+    /// Returns None if the move doesn't capture or if there is no piece of the current player on the source
+    pub fn captured_piece(&self, chess_move: ChessMove) -> Option<PieceType> {
+        let bb_src = chess_move.src.as_bb();
+        let bb_dst = self.to_internal_dst(chess_move.src, chess_move.dst).as_bb();
+        if chess_move.drop.is_some() || !self.inner.has_square_movable_piece(bb_src) {
+            None
+        } else if self.inner.is_en_passant(bb_src, bb_dst) {
+            Some(PieceType::Pawn)
+        } else if self.inner.is_capture(bb_src, bb_dst) {
+            Some(self.inner.get_piece_type(bb_dst))
         } else {
-            GameState::Playing
+            None
+        }
+    }
+
+    /// Returns the static exchange evaluation of the move, the material in centipawns by SEE_VALUES the current player wins
+    ///     when both players then capture on the destination with their least valuable piece, and stop when capturing would lose material
+    /// Pieces behind the capturing pieces join the exchange, pins are ignored and castling is 0
+    /// The move is not checked for legality and the rules of standard chess are used
+    pub fn see(&self, chess_move: ChessMove) -> i32 {
+        let bb_src = chess_move.src.as_bb();
+        let dst = self.to_internal_dst(chess_move.src, chess_move.dst);
+        if let Some(piece_type) = chess_move.drop {
+            return static_exchange(&self.inner, 0, dst.get(), piece_type, None);
+        }
+        if !self.inner.has_square_movable_piece(bb_src) || self.inner.is_castle(bb_src, dst.as_bb()) {
+            return 0;
+        }
+
+        let piece_type = chess_move.promotion.unwrap_or(self.inner.get_piece_type(bb_src));
+        let captured = if self.inner.is_en_passant(bb_src, dst.as_bb()) {
+            let en_passant_square = self.inner.en_passant_mask.trailing_zeros() as usize;
+            let opposite_color = PieceColor::opposite(self.inner.current_color) as usize;
+            Some((PieceType::Pawn, BBMASKS.pieces.en_passant_attacks[opposite_color][en_passant_square]))
+        } else {
+            self.captured_piece(chess_move).map(|captured| (captured, dst.as_bb()))
         };
-        ChessBoardInfo {
-            player_turn: self.inner.current_color,
-            is_current_player_in_check: is_current_player_in_check,
-            game_state:  game_state,
+        static_exchange(&self.inner, bb_src, dst.get(), piece_type, captured)
+    }
+
+    /// Returns true if the static exchange evaluation of the move is at least "threshold"
+    pub fn see_ge(&self, chess_move: ChessMove, threshold: i32) -> bool {
+        self.see(chess_move) >= threshold
+    }
+
+    /// Returns the pieces "color" can drop, which is always empty outside of variants such as Crazyhouse
+    pub fn pocket(&self, color: PieceColor) -> Pocket {
+        self.inner.pockets[color as usize]
+    }
+
+    /// Returns the legal move described by a move in UCI long algebraic notation, for example "e2e4" or "e7e8q"
+    /// Returns None if there is no such legal move
+    pub fn parse_move(&self, uci: &str) -> Option<ChessMove> {
+        self.legal_moves().into_iter().find(|chess_move| chess_move.to_string() == uci)
+    }
+
+    /// Returns true if castling moves are written as the king capturing its own rook, for example "e1h1" instead of "e1g1"
+    /// This is the case for positions which can only be reached in Chess960, or if it has been set with set_chess960
+    pub fn chess960(&self) -> bool {
+        self.inner.chess960
+    }
+
+    /// Sets if castling moves are written as the king capturing its own rook, as is done in Chess960
+    /// The castling rules don't change, the standard rules are a special case of the Chess960 rules
+    pub fn set_chess960(&mut self, chess960: bool) {
+        self.inner.chess960 = chess960;
+    }
+
+    /// Returns the Chess960 start position with the given index, 518 is the standard start position
+    /// https://www.chessprogramming.org/Reinhard_Scharnagl#Chess960_Numbering_Scheme
+    /// Returns None if the index is not in 0..960
+    pub fn from_chess960_index(index: usize) -> Option<Self> {
+        const KNIGHTS: [(usize, usize); 10] = [ (0, 1), (0, 2), (0, 3), (0, 4), (1, 2), (1, 3), (1, 4), (2, 3), (2, 4), (3, 4) ];
+        if index >= 960 {
+            return None;
         }
+
+        // Files are counted from the a-file, every piece is placed on the n-th empty square
+        let mut back_rank = [' '; BOARD_FILES];
+        let place = |back_rank: &mut [char; BOARD_FILES], piece: char, n: usize| {
+            let file = (0..BOARD_FILES).filter(|file| back_rank[*file] == ' ').nth(n).unwrap();
+            back_rank[file] = piece;
+        };
+        let mut n = index;
+        back_rank[(n % 4) * 2 + 1] = 'b';
+        n /= 4;
+        back_rank[(n % 4) * 2] = 'b';
+        n /= 4;
+        place(&mut back_rank, 'q', n % 6);
+        n /= 6;
+        let (first_knight, second_knight) = KNIGHTS[n];
+        place(&mut back_rank, 'n', second_knight);
+        place(&mut back_rank, 'n', first_knight);
+        place(&mut back_rank, 'r', 0);
+        place(&mut back_rank, 'k', 0);
+        place(&mut back_rank, 'r', 0);
+
+        let black: String = back_rank.iter().collect();
+        let fen = format!("{}/pppppppp/8/8/8/8/PPPPPPPP/{} w KQkq - 0 1", black, black.to_ascii_uppercase());
+        let mut chess_board = ChessBoard::new(Some(&fen))?;
+        chess_board.set_chess960(true);
+        Some(chess_board)
     }
-}
 
+    // Castling is generated as the king capturing its own rook, outside of Chess960 it is written as the king moving two squares
+    pub(crate) fn to_public_dst(&self, src: Index, dst: Index) -> Index {
+        if self.inner.chess960 || !self.inner.is_castle(src.as_bb(), dst.as_bb()) {
+            return dst;
+        }
+        let side = CastlingSide::of_rook(src.get(), dst.get());
+        Index::new(square_index(src.get_rank().get(), CASTLING_KING_FILES[side as usize])).unwrap()
+    }
 
+    pub(crate) fn to_internal_dst(&self, src: Index, dst: Index) -> Index {
+        let bb_src = src.as_bb();
+        if self.inner.chess960 || !self.inner.has_square_movable_piece(bb_src) || self.inner.get_piece_type(bb_src) != PieceType::King
+                || src.get_rank() != dst.get_rank() || src.get_file().get().abs_diff(dst.get_file().get()) != 2 {
+            return dst;
+        }
+        let side = CastlingSide::of_rook(src.get(), dst.get());
+        match self.inner.castling_rook_square(self.inner.current_color, side) {
+            Some(rook_square) => Index::new(rook_square).unwrap(),
+            None => dst,
+        }
+    }
 
+    /// Returns the Zobrist hash of the position
+    /// Positions which are equal, including side to move, castling availability and en passant square, have the same hash
+    pub fn hash(&self) -> u64 {
+        self.inner.hash()
+    }
+
+    /// Returns false if "color" can't win by any series of legal moves, following the rules of the variant
+    pub fn has_mating_material(&self, color: PieceColor) -> bool {
+        self.variant.has_mating_material(self, color)
+    }
+
+    /// Returns false if "color" can't checkmate the opponent by any series of legal moves
+    /// This is the case when "color" only has its king, a king and a knight against a lone king,
+    ///     or a king and bishops on squares of one color against a king and bishops on squares of the same color
+    pub fn has_standard_mating_material(&self, color: PieceColor) -> bool {
+        let own = self.inner.all_pieces[color as usize];
+        let opponent = self.inner.all_pieces[PieceColor::opposite(color) as usize];
+        let kings = self.inner.pieces[PieceType::King as usize];
+        let knights = self.inner.pieces[PieceType::Knight as usize];
+        let bishops = self.inner.pieces[PieceType::Bishop as usize];
+
+        let own_pieces = own & !kings;
+        let opponent_pieces = opponent & !kings;
+        if own_pieces == 0 {
+            return false;
+        }
+        if own_pieces & knights == own_pieces && own_pieces.count_ones() == 1 {
+            return opponent_pieces != 0;
+        }
+
+        // Bishops on squares of one color can never attack the squares of the other color, so the king can always escape
+        let all_bishops = own_pieces | opponent_pieces;
+        let same_colored_bishops = all_bishops & DARK_SQUARES == all_bishops || all_bishops & DARK_SQUARES == 0;
+        !(all_bishops & bishops == all_bishops && same_colored_bishops)
+    }
+}
@@ -0,0 +1,222 @@
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{ AtomicU64, Ordering };
+use std::time::{ Duration, Instant };
+
+use crate::chess_board::*;
+
+/// The source of the time measured by a clock
+/// Now returns the time passed since an arbitrary point, it should never decrease
+pub trait TimeSource {
+    fn now(&self) -> Duration;
+}
+
+/// Measures the real time with a monotonic clock
+#[derive(Debug, Clone, Copy)]
+pub struct SystemTimeSource {
+    start: Instant,
+}
+
+impl Default for SystemTimeSource {
+    fn default() -> Self {
+        SystemTimeSource { start: Instant::now() }
+    }
+}
+
+impl TimeSource for SystemTimeSource {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+/// A time source which only moves when it is advanced, for example in tests
+/// Clones share the same time, so a clone can be given to a clock and the original advanced
+#[derive(Debug, Clone, Default)]
+pub struct FakeTimeSource {
+    nanos: Arc<AtomicU64>,
+}
+
+impl FakeTimeSource {
+    pub fn new() -> Self {
+        FakeTimeSource::default()
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        self.nanos.fetch_add(duration.as_nanos() as u64, Ordering::Relaxed);
+    }
+}
+
+impl TimeSource for FakeTimeSource {
+    fn now(&self) -> Duration {
+        Duration::from_nanos(self.nanos.load(Ordering::Relaxed))
+    }
+}
+
+/// How the time of a player changes with every move
+/// Increment: The time is added after every move (Fischer)
+/// Bronstein: The time used by the move is added after it, up to the delay
+/// Simple: The time of the player doesn't start to run until the delay has passed (US delay)
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Delay {
+    None,
+    Increment(Duration),
+    Bronstein(Duration),
+    Simple(Duration),
+}
+
+/// A period of a time control, "time" is added to the remaining time of a player when the period starts
+/// Moves is the number of moves which has to be made within the period, None if it lasts for the rest of the game
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct TimePeriod {
+    pub moves: Option<u32>,
+    pub time: Duration,
+    pub delay: Delay,
+}
+
+/// The periods of a time control, played in order
+/// If the last period has a number of moves it is repeated, for example "40/120, then 20/60 repeating"
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TimeControl {
+    pub periods: Vec<TimePeriod>,
+}
+
+impl TimeControl {
+    /// Returns a time control with a single period lasting the whole game
+    pub fn new(time: Duration, delay: Delay) -> Self {
+        TimeControl { periods: vec![ TimePeriod { moves: None, time, delay } ] }
+    }
+
+    // Every period after the last one is the last one, either repeated or lasting for the rest of the game
+    fn period(&self, index: usize) -> &TimePeriod {
+        &self.periods[index.min(self.periods.len() - 1)]
+    }
+}
+
+/// A chess clock, the clock of at most one player runs at a time
+/// A player whose remaining time reaches zero has lost on time, the clock stops counting for them
+/// Clones share the time source
+#[derive(Clone)]
+pub struct Clock {
+    time_source: Arc<dyn TimeSource + Send + Sync>,
+    time_control: TimeControl,
+    remaining: [Duration; 2],
+    periods: [usize; 2],
+    period_moves: [u32; 2],      // The number of moves made in the current period
+    turn: Option<(PieceColor, Duration)>,   // The player whose clock is running and when it was started
+}
+
+impl Clock {
+    /// Returns a stopped clock measuring the real time
+    /// Panics if the time control has no periods
+    pub fn new(time_control: TimeControl) -> Self {
+        Clock::with_time_source(time_control, SystemTimeSource::default())
+    }
+
+    /// Returns a stopped clock measuring the time of the given time source
+    /// Panics if the time control has no periods
+    pub fn with_time_source(time_control: TimeControl, time_source: impl TimeSource + Send + Sync + 'static) -> Self {
+        assert!(!time_control.periods.is_empty());
+        let time = time_control.periods[0].time;
+        Clock {
+            time_source: Arc::new(time_source),
+            time_control,
+            remaining: [time; 2],
+            periods: [0; 2],
+            period_moves: [0; 2],
+            turn: None,
+        }
+    }
+
+    pub fn time_control(&self) -> &TimeControl {
+        &self.time_control
+    }
+
+    /// Returns the player whose clock is running
+    pub fn running(&self) -> Option<PieceColor> {
+        self.turn.map(|(color, _)| color)
+    }
+
+    /// Starts the clock of "color", stopping the clock of the other player without ending their move
+    pub fn start(&mut self, color: PieceColor) {
+        self.stop();
+        self.turn = Some((color, self.time_source.now()));
+    }
+
+    /// Stops the running clock, the player is charged for the time used but gets no increment
+    pub fn stop(&mut self) {
+        if let Some((color, _)) = self.turn {
+            self.remaining[color as usize] = self.remaining(color);
+            self.turn = None;
+        }
+    }
+
+    /// Ends the move of the player whose clock is running, and starts the clock of the other player
+    /// Returns the remaining time of the player who moved, including the increment
+    /// Returns None if no clock is running or if the player has run out of time, which stops the clock
+    pub fn press(&mut self) -> Option<Duration> {
+        let (color, started) = self.turn?;
+        let elapsed = self.time_source.now().saturating_sub(started);
+        let remaining = self.remaining(color);
+        if remaining.is_zero() {
+            self.stop();
+            return None;
+        }
+
+        let index = color as usize;
+        let period = *self.time_control.period(self.periods[index]);
+        self.remaining[index] = remaining + match period.delay {
+            Delay::Increment(increment) => increment,
+            Delay::Bronstein(delay) => elapsed.min(delay),
+            Delay::None | Delay::Simple(_) => Duration::ZERO,
+        };
+
+        // The time of the next period is added when the moves of the current one have been made
+        self.period_moves[index] += 1;
+        if period.moves == Some(self.period_moves[index]) {
+            self.periods[index] += 1;
+            self.period_moves[index] = 0;
+            self.remaining[index] += self.time_control.period(self.periods[index]).time;
+        }
+
+        self.turn = Some((PieceColor::opposite(color), self.time_source.now()));
+        Some(self.remaining[index])
+    }
+
+    /// Returns the remaining time of "color", including the time used by the current move if its clock is running
+    pub fn remaining(&self, color: PieceColor) -> Duration {
+        let remaining = self.remaining[color as usize];
+        match self.turn {
+            Some((running, started)) if running == color => {
+                let elapsed = self.time_source.now().saturating_sub(started);
+                let used = match self.time_control.period(self.periods[color as usize]).delay {
+                    Delay::Simple(delay) => elapsed.saturating_sub(delay),
+                    _ => elapsed,
+                };
+                remaining.saturating_sub(used)
+            }
+            _ => remaining,
+        }
+    }
+
+    /// Returns the number of moves "color" has left to make in the current period, None if the period lasts for the rest of the game
+    pub fn moves_to_go(&self, color: PieceColor) -> Option<u32> {
+        let period = self.time_control.period(self.periods[color as usize]);
+        period.moves.map(|moves| moves - self.period_moves[color as usize])
+    }
+
+    /// Returns the player who has run out of time, if any
+    pub fn flagged(&self) -> Option<PieceColor> {
+        [PieceColor::White, PieceColor::Black].into_iter().find(|color| self.remaining(*color).is_zero())
+    }
+}
+
+impl fmt::Debug for Clock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Clock")
+            .field("time_control", &self.time_control)
+            .field("white", &self.remaining(PieceColor::White))
+            .field("black", &self.remaining(PieceColor::Black))
+            .field("running", &self.running())
+            .finish()
+    }
+}
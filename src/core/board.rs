@@ -1,4 +1,3 @@
-use bitflags::bitflags;
 pub type BitBoard = u64;
 
 pub const BOARD_RANKS: usize = 8;
@@ -7,16 +6,34 @@ pub const BOARD_SIZE: usize = BOARD_RANKS * BOARD_FILES;
 
 pub type BySquare<T = BitBoard> = [T; BOARD_SIZE];
 
-bitflags! {
-    #[derive(PartialEq, Clone, Copy, Debug)]
-    pub struct CastlingAvailability: usize {
-        const None      = 0;
-        const KingSide  = 1;
-        const QueenSide = 2;
+// The first and last rank, where pawns get promoted
+pub const PROMOTION_RANKS: BitBoard = 0xFF000000000000FF;
+
+// The dark squares, a1 is dark
+pub const DARK_SQUARES: BitBoard = 0x55AA55AA55AA55AA;
+
+// The king side is the side of the h-file, the side of a rook is decided by its file relative to the king
+#[repr(usize)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum CastlingSide {
+    KingSide = 0,
+    QueenSide = 1,
+}
+
+impl CastlingSide {
+    pub const fn of_rook(king_square: usize, rook_square: usize) -> CastlingSide {
+        if file_index(rook_square) < file_index(king_square) { CastlingSide::KingSide } else { CastlingSide::QueenSide }
     }
 }
 
-pub const CASTLING_AVAILABILITY_SIZE: usize = 4;
+pub const CASTLING_SIDE_COUNT: usize = 2;
+
+pub type BySide<T> = [T; CASTLING_SIDE_COUNT];
+
+pub const CASTLING_SIDES: BySide<CastlingSide> = [ CastlingSide::KingSide, CastlingSide::QueenSide ];
+// The files the king and the rook end on after castling, the g- and f-file on the king side, the c- and d-file on the queen side
+pub const CASTLING_KING_FILES: BySide<usize> = [ 1, 5 ];
+pub const CASTLING_ROOK_FILES: BySide<usize> = [ 2, 4 ];
 
 pub const fn square_index(rank: usize, file: usize) -> usize {
     rank * BOARD_FILES + file
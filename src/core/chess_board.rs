@@ -2,17 +2,23 @@ use crate::core::move_generation::get_pieces_attacking_king;
 use super::board::*;
 use super::piece::*;
 use super::precompute_masks::*;
+use super::zobrist::{ self, ZOBRIST };
 
 #[derive(Clone, Debug)]
 pub struct ChessBoard {
     pub all_pieces: ByColor<BitBoard>,
 
     pub current_color: PieceColor,
-    pub castling_availability: ByColor<CastlingAvailability>,
     pub en_passant_mask: BitBoard,     // Contains the square a pawn has just passed while moving two squares
     pub promotion_mask: BitBoard,
     pub half_moves: u32,            // Half moves since last pawn move or capture. Used for fify-move rule
     pub full_moves: u32,            // Full moves since start
+    pub castling_rook_files: ByColor<BySide<Option<usize>>>,   // The file of the rook each color can castle with, None if it can't castle on that side
+    pub chess960: bool,             // Castling moves are written as the king capturing its own rook
+    pub checks: ByColor<u32>,       // The number of checks each color has given, only counted by variants such as Three-check
+    pub pockets: ByColor<Pocket>,   // The pieces each color can drop, only used by variants such as Crazyhouse
+    pub promoted: BitBoard,         // The pieces which were pawns before they were promoted
+    key: u64,                       // The Zobrist hash without the en passant square, updated with every change of the position
 
     pub pieces: ByPiece<BitBoard>,
 }
@@ -23,15 +29,42 @@ impl ChessBoard {
         let mut chess_board: ChessBoard = ChessBoard { 
             all_pieces: [0, 0],
             current_color: PieceColor::White, 
-            castling_availability: [CastlingAvailability::None; PIECE_COLOR_COUNT],
             en_passant_mask: 0, 
             promotion_mask: 0,
             half_moves: 0, 
             full_moves: 0, 
+            castling_rook_files: [[None; CASTLING_SIDE_COUNT]; PIECE_COLOR_COUNT],
+            chess960: false,
+            checks: [0; PIECE_COLOR_COUNT],
+            pockets: [Pocket::default(); PIECE_COLOR_COUNT],
+            promoted: 0,
+            key: 0,
             pieces: [0; PIECE_TYPE_COUNT],
         };
 
+        // The pockets of Crazyhouse follow the placement in brackets, for example "[Nq]", and "~" marks a promoted piece
         pub fn handle_placement_encoding(placement: &str, chess_board: &mut ChessBoard) -> bool {
+            let placement = match placement.split_once('[') {
+                None => placement,
+                Some((placement, pockets)) => {
+                    let Some(pockets) = pockets.strip_suffix(']') else {
+                        return false;
+                    };
+                    for chr in pockets.chars() {
+                        let color = if chr.is_ascii_uppercase() { PieceColor::White } else { PieceColor::Black };
+                        match PieceType::from_char(chr) {
+                            Some(piece_type) if piece_type != PieceType::King => {
+                                if !chess_board.pockets[color as usize].add(piece_type) {
+                                    return false;
+                                }
+                            },
+                            _ => return false,
+                        }
+                    }
+                    placement
+                }
+            };
+
             let mut square_index: usize = BOARD_SIZE;
             for rank in placement.split("/") {
                 for chr in rank.chars() {
@@ -39,6 +72,13 @@ impl ChessBoard {
                         square_index -= skips as usize;
                         continue;
                     }
+                    if chr == '~' {
+                        if square_index == BOARD_SIZE {
+                            return false;
+                        }
+                        chess_board.promoted |= (1 as BitBoard) << square_index;
+                        continue;
+                    }
                     square_index -= 1;
                     assert!(square_index < BOARD_SIZE);
                     // TODO: also update all_white / all_black
@@ -62,25 +102,56 @@ impl ChessBoard {
                     *piece_type |= square;
                 }
             }
-            return true;
+            true
         }
 
         fn handle_turn_encoding(turn: &str, chess_board: &mut ChessBoard) {
             if turn == "w" { chess_board.current_color = PieceColor::White } else { chess_board.current_color = PieceColor::Black }
         }
 
+        // Supports the standard, Shredder-FEN and X-FEN castling fields
+        // KQkq is the outermost rook on that side of the king, a file letter is the rook on that file
+        // The colors may castle with rooks on different files, as in Double Fischer Random
+        // A right without its king or rook on the back rank is dropped, as python-chess and Stockfish do, since FEN strings often keep stale rights
         fn handle_castling_availability_encoding(availabilities: &str, chess_board: &mut ChessBoard) {
             for availability in availabilities.chars() {
-                match availability {
-                    'K' => chess_board.castling_availability[PieceColor::White as usize] |= CastlingAvailability::KingSide,
-                    'Q' => chess_board.castling_availability[PieceColor::White as usize] |= CastlingAvailability::QueenSide,
-                    'k' => chess_board.castling_availability[PieceColor::Black as usize] |= CastlingAvailability::KingSide,
-                    'q' => chess_board.castling_availability[PieceColor::Black as usize] |= CastlingAvailability::QueenSide,
-                    '-' => { chess_board.castling_availability[PieceColor::White as usize] = CastlingAvailability::None;
-                             chess_board.castling_availability[PieceColor::Black as usize] = CastlingAvailability::None;
+                if availability == '-' {
+                    continue;
+                }
+                let color = if availability.is_ascii_uppercase() { PieceColor::White } else { PieceColor::Black };
+                let rank = ChessBoard::back_rank(color);
+                let own_pieces = chess_board.all_pieces[color as usize];
+                let king = chess_board.pieces[PieceType::King as usize] & own_pieces & (0xFF << (rank * BOARD_FILES));
+                let rooks = chess_board.pieces[PieceType::Rook as usize] & own_pieces;
+                if king == 0 {
+                    continue;
+                }
+                let king_file = file_index(king.trailing_zeros() as usize);
+
+                let rook_file = match availability.to_ascii_lowercase() {
+                    'k' => (0..king_file).find(|file| rooks & ((1 as BitBoard) << square_index(rank, *file)) != 0),
+                    'q' => (king_file + 1..BOARD_FILES).rev().find(|file| rooks & ((1 as BitBoard) << square_index(rank, *file)) != 0),
+                    file @ 'a'..='h' => {
+                        chess_board.chess960 = true;
+                        Some(BOARD_FILES - 1 - (file as u8 - b'a') as usize)
                     },
-                    _ => (),
+                    _ => continue,
+                };
+                let rook_file = match rook_file {
+                    Some(file) if file != king_file && rooks & ((1 as BitBoard) << square_index(rank, file)) != 0 => file,
+                    _ => continue,
                 };
+
+                let side = if rook_file < king_file { CastlingSide::KingSide } else { CastlingSide::QueenSide };
+                if chess_board.castling_rook_files[color as usize][side as usize].is_some_and(|file| file != rook_file) {
+                    continue;
+                }
+                chess_board.castling_rook_files[color as usize][side as usize] = Some(rook_file);
+
+                // Any other king or rook position can only be reached in Chess960
+                if king_file != 3 || rook_file != [ 0, BOARD_FILES - 1 ][side as usize] {
+                    chess_board.chess960 = true;
+                }
             }
         }
 
@@ -116,12 +187,36 @@ impl ChessBoard {
                         Ok(val) => val,
                     }
                 },
+                // The checks given by white and black in Three-check, for example "+2+0"
+                6 => {
+                    let mut checks = field.split('+').skip(1).map(|checks| checks.parse::<u32>());
+                    match (field.starts_with('+'), checks.next(), checks.next(), checks.next()) {
+                        (true, Some(Ok(white)), Some(Ok(black)), None) => chess_board.checks = [ white, black ],
+                        _ => return None,
+                    }
+                },
                 _ => return None,
             }
         }
+        chess_board.key = zobrist::key(&chess_board);
         Some(chess_board)
     }
 
+    pub const fn back_rank(color: PieceColor) -> usize {
+        match color {
+            PieceColor::White => 0,
+            PieceColor::Black => BOARD_RANKS - 1,
+        }
+    }
+
+    // The square of the rook "color" castles with on "side", None if it can't castle on that side
+    pub const fn castling_rook_square(&self, color: PieceColor, side: CastlingSide) -> Option<usize> {
+        match self.castling_rook_files[color as usize][side as usize] {
+            Some(file) => Some(square_index(ChessBoard::back_rank(color), file)),
+            None => None,
+        }
+    }
+
     pub const fn all_pieces(&self) -> BitBoard {
         self.all_pieces[PieceColor::White as usize] | self.all_pieces[PieceColor::Black as usize]
     }
@@ -163,11 +258,59 @@ impl ChessBoard {
     pub fn is_current_player_in_check(&self) -> bool {
         get_pieces_attacking_king(self, PieceColor::opposite(self.current_color)) != 0
     }
+
+    // The Zobrist hash of the position, without hashing the pieces again
+    // The fields are public, so a board changed without the methods below would have a stale key
+    pub fn hash(&self) -> u64 {
+        debug_assert_eq!(self.key, zobrist::key(self));
+        self.key ^ zobrist::en_passant_key(self)
+    }
 }
 
 impl ChessBoard {
     pub fn toggle_current_color(&mut self) {
         self.current_color = PieceColor::opposite(self.current_color);
+        self.key ^= ZOBRIST.black_to_move;
+    }
+
+    // Adds the pieces on "bb_squares" to the key, or removes them if they are already in it
+    fn toggle_piece_keys(&mut self, color: PieceColor, piece_type: PieceType, mut bb_squares: BitBoard) {
+        while bb_squares != 0 {
+            let square = pop_lsb(&mut bb_squares);
+            self.key ^= ZOBRIST.pieces[color as usize][piece_type as usize][square];
+        }
+    }
+
+    fn set_castling_rook_file(&mut self, color: PieceColor, side: CastlingSide, file: Option<usize>) {
+        let castling_keys = &ZOBRIST.castling[color as usize][side as usize];
+        if let Some(previous) = self.castling_rook_files[color as usize][side as usize] {
+            self.key ^= castling_keys[previous];
+        }
+        if let Some(file) = file {
+            self.key ^= castling_keys[file];
+        }
+        self.castling_rook_files[color as usize][side as usize] = file;
+    }
+
+    fn clear_castling_rook_files(&mut self, color: PieceColor) {
+        for side in CASTLING_SIDES {
+            self.set_castling_rook_file(color, side, None);
+        }
+    }
+
+    // Counts a check given by "color", as in Three-check
+    pub fn add_check(&mut self, color: PieceColor) {
+        self.key ^= zobrist::check_key(color, self.checks[color as usize]);
+        self.checks[color as usize] += 1;
+        self.key ^= zobrist::check_key(color, self.checks[color as usize]);
+    }
+
+    // Legal play never fills a pocket past Pocket::MAX_COUNT, only a FEN string could
+    pub fn add_to_pocket(&mut self, color: PieceColor, piece_type: PieceType) {
+        let pocket_keys = &ZOBRIST.pockets[color as usize][piece_type as usize];
+        let previous = self.pockets[color as usize].count(piece_type);
+        assert!(self.pockets[color as usize].add(piece_type));
+        self.key ^= pocket_keys[previous as usize] ^ pocket_keys[previous as usize + 1];
     }
 
     pub fn make_move(&mut self, square: usize, bb_move: BitBoard) {
@@ -178,17 +321,40 @@ impl ChessBoard {
         let move_square = bb_move.trailing_zeros() as usize;
         let piece_type = self.get_piece_type(bb_square);
 
+        self.update_move_counters(bb_move, piece_type);
+        if self.is_castle(bb_square, bb_move) {
+            self.castle(square, move_square);
+            return;
+        }
         self.clear_destination(bb_move);
         self.move_piece(bb_square, bb_move, piece_type);
         self.update_castling_rights(square, move_square, piece_type);
         self.update_en_passant(square, bb_move, move_square, piece_type);
     }
 
+    fn update_move_counters(&mut self, bb_move: BitBoard, piece_type: PieceType) {
+        // The clock of the fifty-move rule is reset by pawn moves and captures
+        if piece_type == PieceType::Pawn || bb_move & self.all_pieces[PieceColor::opposite(self.current_color) as usize] != 0 {
+            self.half_moves = 0;
+        } else {
+            self.half_moves += 1;
+        }
+        if self.current_color == PieceColor::Black {
+            self.full_moves += 1;
+        }
+    }
+
     // Remove destination from all bit boards
     fn clear_destination(&mut self, bb_move: BitBoard) {
+        let opposite_color = PieceColor::opposite(self.current_color);
+        if bb_move & self.all_pieces[opposite_color as usize] != 0 {
+            let captured = self.get_piece_type(bb_move);
+            self.toggle_piece_keys(opposite_color, captured, bb_move);
+        }
         for piece_type in [PieceType::Pawn, PieceType::Knight, PieceType::Bishop, PieceType::Rook, PieceType::Queen, PieceType::King ] {
             self.pieces[piece_type as usize] &= !bb_move;
         }
+        self.promoted &= !bb_move;
 
         // Only clear opposite color because you can't stack same color pieces
         self.all_pieces[PieceColor::opposite(self.current_color) as usize] &= !bb_move;
@@ -201,29 +367,93 @@ impl ChessBoard {
         // Add new destination piece
         self.pieces[piece_type as usize] |= bb_move;
         self.all_pieces[self.current_color as usize] |= bb_move;
+        self.toggle_piece_keys(self.current_color, piece_type, bb_square | bb_move);
+        if self.promoted & bb_square != 0 {
+            self.promoted = (self.promoted & !bb_square) | bb_move;
+        }
     }
 
-    fn update_castling_rights(&mut self, square: usize, move_square: usize, piece_type: PieceType) {
-        // Removes castling availability if capture of enemy rook
-        let opposite_color = PieceColor::opposite(self.current_color) as usize;
-        self.castling_availability[opposite_color] &= !BBMASKS.pieces.castling_corners[opposite_color][move_square];
+    // Places a piece from the pocket of the current player on the empty square, as in Crazyhouse
+    pub fn drop_piece(&mut self, piece_type: PieceType, square: usize) {
+        let bb_square = (1 as BitBoard) << square;
+        assert!(!self.has_square_piece(bb_square));
+        let pocket_keys = &ZOBRIST.pockets[self.current_color as usize][piece_type as usize];
+        let previous = self.pockets[self.current_color as usize].count(piece_type);
+        assert!(self.pockets[self.current_color as usize].remove(piece_type));
+        self.key ^= pocket_keys[previous as usize] ^ pocket_keys[previous as usize - 1];
+
+        self.update_move_counters(bb_square, piece_type);
+        self.pieces[piece_type as usize] |= bb_square;
+        self.all_pieces[self.current_color as usize] |= bb_square;
+        self.toggle_piece_keys(self.current_color, piece_type, bb_square);
+        self.en_passant_mask = 0;
+    }
+
+    // Removes every piece on "bb_squares" of both colors, as the explosions of Atomic do
+    // A color loses the castling rights of the removed rooks, and all of them if its king is removed
+    pub fn remove_pieces(&mut self, bb_squares: BitBoard) {
+        for piece_type in [PieceType::Pawn, PieceType::Knight, PieceType::Bishop, PieceType::Rook, PieceType::Queen, PieceType::King] {
+            for color in [PieceColor::White, PieceColor::Black] {
+                self.toggle_piece_keys(color, piece_type, self.pieces[piece_type as usize] & self.all_pieces[color as usize] & bb_squares);
+            }
+            self.pieces[piece_type as usize] &= !bb_squares;
+        }
+        self.promoted &= !bb_squares;
+
+        for color in [PieceColor::White, PieceColor::Black] {
+            let bb_king = self.pieces[PieceType::King as usize] & self.all_pieces[color as usize];
+            let king_removed = bb_king & bb_squares != 0;
+            self.all_pieces[color as usize] &= !bb_squares;
+            for side in CASTLING_SIDES {
+                let rook_removed = matches!(self.castling_rook_square(color, side), Some(square) if (1 as BitBoard) << square & bb_squares != 0);
+                if king_removed || rook_removed {
+                    self.set_castling_rook_file(color, side, None);
+                }
+            }
+        }
+    }
 
-        // Remove castling availability if rook moves
-        let removed_castling_availability = BBMASKS.pieces.castling_corners[self.current_color as usize][square];
-        self.castling_availability[self.current_color as usize] &= !removed_castling_availability;
+    fn update_castling_rights(&mut self, square: usize, move_square: usize, piece_type: PieceType) {
+        let current_color = self.current_color;
+        let opposite_color = PieceColor::opposite(current_color);
+        for side in CASTLING_SIDES {
+            // Removes castling availability if capture of enemy rook
+            if self.castling_rook_square(opposite_color, side) == Some(move_square) {
+                self.set_castling_rook_file(opposite_color, side, None);
+            }
+            // Remove castling availability if rook moves
+            if self.castling_rook_square(current_color, side) == Some(square) {
+                self.set_castling_rook_file(current_color, side, None);
+            }
+        }
 
         if piece_type == PieceType::King {
-            // Move rook if castling
-            let mask = BBMASKS.pieces.castling_rook_moves[self.current_color as usize][self.castling_availability[self.current_color as usize].bits()][move_square];
-            assert!(self.castling_availability[self.current_color as usize].bits() <= 3);
-            self.pieces[PieceType::Rook as usize] ^= mask;
-            self.all_pieces[self.current_color as usize] ^= mask;
-
-            // Clear own castling availability
-            self.castling_availability[self.current_color as usize] = CastlingAvailability::None;
+            self.clear_castling_rook_files(current_color);
         }
     }
 
+    // Castling is encoded as the king capturing its own rook, since the king might not move or end on the square of the rook in Chess960
+    fn castle(&mut self, king_square: usize, rook_square: usize) {
+        let color = self.current_color as usize;
+        let rank = rank_index(king_square);
+        let side = CastlingSide::of_rook(king_square, rook_square);
+        let bb_king = (1 as BitBoard) << king_square;
+        let bb_rook = (1 as BitBoard) << rook_square;
+        let bb_king_destination = (1 as BitBoard) << square_index(rank, CASTLING_KING_FILES[side as usize]);
+        let bb_rook_destination = (1 as BitBoard) << square_index(rank, CASTLING_ROOK_FILES[side as usize]);
+
+        self.pieces[PieceType::King as usize] = (self.pieces[PieceType::King as usize] & !bb_king) | bb_king_destination;
+        self.pieces[PieceType::Rook as usize] = (self.pieces[PieceType::Rook as usize] & !bb_rook) | bb_rook_destination;
+        self.all_pieces[color] = (self.all_pieces[color] & !(bb_king | bb_rook)) | bb_king_destination | bb_rook_destination;
+        self.toggle_piece_keys(self.current_color, PieceType::King, bb_king);
+        self.toggle_piece_keys(self.current_color, PieceType::King, bb_king_destination);
+        self.toggle_piece_keys(self.current_color, PieceType::Rook, bb_rook);
+        self.toggle_piece_keys(self.current_color, PieceType::Rook, bb_rook_destination);
+
+        self.clear_castling_rook_files(self.current_color);
+        self.en_passant_mask = 0;
+    }
+
     fn update_en_passant(&mut self, square: usize, bb_move: BitBoard, move_square: usize, piece_type: PieceType) {
         if piece_type == PieceType::Pawn {
             // En passant
@@ -232,6 +462,8 @@ impl ChessBoard {
                 let en_passant_index = self.en_passant_mask.trailing_zeros() as usize;
                 let opposite_color = PieceColor::opposite(self.current_color) as usize;
                 let mask = !BBMASKS.pieces.en_passant_attacks[opposite_color][en_passant_index];
+                let captured = self.pieces[PieceType::Pawn as usize] & self.all_pieces[opposite_color] & !mask;
+                self.toggle_piece_keys(PieceColor::opposite(self.current_color), PieceType::Pawn, captured);
  
                 self.pieces[PieceType::Pawn as usize] &= mask;
                 self.all_pieces[opposite_color] &= mask;
@@ -256,12 +488,18 @@ impl ChessBoard {
 impl ChessBoard {
     pub fn resolve_promotion(&mut self, piece_type: PieceType) {
         assert!(self.promotion_mask != 0);
-        assert!(piece_type == PieceType::Knight || piece_type == PieceType::Bishop || piece_type == PieceType::Rook || piece_type == PieceType::Queen);
+        // Antichess also allows promoting to a king
+        assert!(piece_type != PieceType::Pawn);
+
+        let color = if self.all_pieces[PieceColor::White as usize] & self.promotion_mask != 0 { PieceColor::White } else { PieceColor::Black };
+        self.toggle_piece_keys(color, PieceType::Pawn, self.promotion_mask);
+        self.toggle_piece_keys(color, piece_type, self.promotion_mask);
 
         // Remove the pawn
         self.pieces[PieceType::Pawn as usize] &= !self.promotion_mask;
         // Add the new piece
         self.pieces[piece_type as usize] |= self.promotion_mask;
+        self.promoted |= self.promotion_mask;
         // Remove promotion mask
         self.promotion_mask = 0;
     }
@@ -278,26 +516,26 @@ impl ChessBoard {
                 && (bb_move & self.en_passant_mask) != 0
     }
 
+    // Castling is the king moving to the square of its own rook
     pub fn is_castle(&self, bb_square: BitBoard, bb_move: BitBoard) -> bool {
         assert!(bb_move != 0);
         assert!(bb_square != 0);
         
-        let square = bb_square.trailing_zeros() as usize;
         let piece_type = self.get_piece_type(bb_square);
-        let mask = BBMASKS.pieces.castling_moves[self.current_color as usize][self.castling_availability[self.current_color as usize].bits()][square];
         piece_type == PieceType::King 
-            && (mask & bb_move) != 0
+            && (self.all_pieces[self.current_color as usize] & bb_move) != 0
     }
 
     pub fn is_capture(&self, bb_square: BitBoard, bb_move: BitBoard) -> bool {
         assert!(bb_move != 0);
         assert!(bb_square != 0);
 
-        self.has_square_piece(bb_move)
+        self.all_pieces[PieceColor::opposite(self.current_color) as usize] & bb_move != 0
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::mixed_case_hex_literals)]
 mod tests {
     use super::*;
 
@@ -346,5 +584,14 @@ mod tests {
         assert_eq!(chess_board.en_passant_mask, 0);
         assert_eq!(chess_board.half_moves, 0);
         assert_eq!(chess_board.full_moves, 1);
+
+        // Crazyhouse pockets and a promoted queen on d8
+        let chess_board = ChessBoard::new("3Q~k3/8/8/8/8/8/8/4K3[NNpq] b - - 0 1").unwrap();
+        assert_eq!(chess_board.pieces[PieceType::Queen as usize], 0x1000000000000000);
+        assert_eq!(chess_board.promoted, 0x1000000000000000);
+        assert_eq!(chess_board.pockets[PieceColor::White as usize].count(PieceType::Knight), 2);
+        assert_eq!(chess_board.pockets[PieceColor::Black as usize].count(PieceType::Pawn), 1);
+        assert_eq!(chess_board.pockets[PieceColor::Black as usize].count(PieceType::Queen), 1);
+        assert!(ChessBoard::new("4k3/8/8/8/8/8/8/4K3[NK] w - - 0 1").is_none());
     }
 }
@@ -61,7 +61,7 @@ impl Dir {
         }
     }
 
-    pub fn opposite(dir: Dir) -> Dir {
+    pub const fn opposite(dir: Dir) -> Dir {
         match dir {
             Dir::North => Dir::South,
             Dir::NorthEast => Dir::SouthWest,
@@ -3,4 +3,6 @@ pub mod chess_board;
 pub mod dir;
 pub mod move_generation;
 pub mod piece;
-pub mod precompute_masks;
\ No newline at end of file
+pub mod precompute_masks;
+pub mod see;
+pub mod zobrist;
\ No newline at end of file
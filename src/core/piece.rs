@@ -1,5 +1,5 @@
 #[repr(usize)]
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub enum PieceType {
     Pawn,
     Knight,
@@ -11,8 +11,23 @@ pub enum PieceType {
 
 pub const PIECE_TYPE_COUNT: usize = 6;
 
+impl PieceType {
+    // The piece type of a letter of a FEN string, for either color
+    pub const fn from_char(chr: char) -> Option<PieceType> {
+        match chr.to_ascii_lowercase() {
+            'p' => Some(PieceType::Pawn),
+            'n' => Some(PieceType::Knight),
+            'b' => Some(PieceType::Bishop),
+            'r' => Some(PieceType::Rook),
+            'q' => Some(PieceType::Queen),
+            'k' => Some(PieceType::King),
+            _ => None,
+        }
+    }
+}
+
 #[repr(usize)]
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub enum PieceColor {
     White = 0,
     Black = 1,
@@ -31,3 +46,42 @@ pub const PIECE_COLOR_COUNT: usize = 2;
 
 pub type ByPiece<T> = [T; PIECE_TYPE_COUNT];
 pub type ByColor<T> = [T; PIECE_COLOR_COUNT];
+
+// The pieces a player holds in hand and can drop on the board instead of moving, as in Crazyhouse and Bughouse
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Pocket {
+    counts: ByPiece<u8>,
+}
+
+impl Pocket {
+    // A color has 16 pieces, even in Bughouse, and promoted pieces go to the pocket as pawns, so no type can exceed it
+    pub const MAX_COUNT: u8 = 16;
+
+    pub const fn count(&self, piece_type: PieceType) -> u8 {
+        self.counts[piece_type as usize]
+    }
+
+    // Returns false if the pocket already holds the most pieces of "piece_type" which can occur
+    pub fn add(&mut self, piece_type: PieceType) -> bool {
+        match self.counts[piece_type as usize].checked_add(1) {
+            Some(count) if count <= Pocket::MAX_COUNT => {
+                self.counts[piece_type as usize] = count;
+                true
+            },
+            _ => false,
+        }
+    }
+
+    // Returns false if there is no piece of "piece_type" in the pocket
+    pub fn remove(&mut self, piece_type: PieceType) -> bool {
+        if self.counts[piece_type as usize] == 0 {
+            return false;
+        }
+        self.counts[piece_type as usize] -= 1;
+        true
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.iter().all(|count| *count == 0)
+    }
+}
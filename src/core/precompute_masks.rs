@@ -4,11 +4,20 @@ use super::piece::*;
 
 pub const BBMASKS: BBMasks = BBMasks::new(); 
 
+// A static instead of a const, since the sliding attack table is too large to be copied into every use
+// Generating all the attack sets takes a few seconds of constant evaluation
+#[allow(long_running_const_eval)]
+pub static MAGICS: magic_masks::Magics = magic_masks::generate(&BBMASKS.rays);
+
 // Contains some precomputed bit board patterns
 pub struct BBMasks {
     pub rays: rays_masks::Rays,
     pub lines: lines_masks::Lines,
     pub pieces: pieces_masks::Pieces,
+    // The squares strictly between two squares, 0 if the squares are not on the same rank, file or diagonal
+    pub between: BySquare<BySquare>,
+    // The whole rank, file or diagonal going through two squares, 0 if the squares are not on the same one
+    pub line: BySquare<BySquare>,
 }
 
 impl BBMasks {
@@ -16,7 +25,9 @@ impl BBMasks {
         let rays = rays_masks::generate();
         let lines = lines_masks::generate(&rays);
         let pieces = pieces_masks::generate(&lines);
-        BBMasks { rays, lines, pieces }
+        let between = segments_masks::generate_between(&rays);
+        let line = segments_masks::generate_line(&rays);
+        BBMasks { rays, lines, pieces, between, line }
     }
 }
 
@@ -184,6 +195,74 @@ mod lines_masks {
     }
 }
 
+mod segments_masks {
+    use super::*;
+
+    pub const fn generate_between(rays: &rays_masks::Rays) -> BySquare<BySquare> {
+        let mut result = [[0; BOARD_SIZE]; BOARD_SIZE];
+
+        let mut src = 0;
+        while src < BOARD_SIZE {
+            let mut dst = 0;
+            while dst < BOARD_SIZE {
+                if let Some(dir) = Dir::FROM_SQUARES_PAIRS[src][dst] {
+                    result[src][dst] = rays[src][dir as usize] & rays[dst][Dir::opposite(dir) as usize];
+                }
+                dst += 1;
+            }
+            src += 1;
+        }
+        result
+    }
+
+    pub const fn generate_line(rays: &rays_masks::Rays) -> BySquare<BySquare> {
+        let mut result = [[0; BOARD_SIZE]; BOARD_SIZE];
+
+        let mut src = 0;
+        while src < BOARD_SIZE {
+            let mut dst = 0;
+            while dst < BOARD_SIZE {
+                if let Some(dir) = Dir::FROM_SQUARES_PAIRS[src][dst] {
+                    result[src][dst] = rays[src][dir as usize] | rays[src][Dir::opposite(dir) as usize] | ((1 as BitBoard) << src);
+                }
+                dst += 1;
+            }
+            src += 1;
+        }
+        result
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        const RAYS: rays_masks::Rays = rays_masks::generate();
+
+        #[test]
+        fn test_between() {
+            let between = generate_between(&RAYS);
+            assert_eq!(between[square_index(0, 3)][square_index(7, 3)], 0x0008080808080800);
+            assert_eq!(between[square_index(7, 3)][square_index(0, 3)], 0x0008080808080800);
+            assert_eq!(between[square_index(0, 0)][square_index(7, 7)], 0x0040201008040200);
+            assert_eq!(between[square_index(3, 4)][square_index(3, 1)], 0x000000000C000000);
+            assert_eq!(between[square_index(3, 4)][square_index(3, 5)], 0);
+            assert_eq!(between[square_index(3, 4)][square_index(5, 5)], 0);
+            assert_eq!(between[square_index(3, 4)][square_index(3, 4)], 0);
+        }
+
+        #[test]
+        fn test_line() {
+            let line = generate_line(&RAYS);
+            assert_eq!(line[square_index(0, 3)][square_index(4, 3)], 0x0808080808080808);
+            assert_eq!(line[square_index(4, 3)][square_index(0, 3)], 0x0808080808080808);
+            assert_eq!(line[square_index(1, 1)][square_index(5, 5)], 0x8040201008040201);
+            assert_eq!(line[square_index(3, 4)][square_index(3, 0)], 0x00000000FF000000);
+            assert_eq!(line[square_index(3, 4)][square_index(5, 5)], 0);
+            assert_eq!(line[square_index(3, 4)][square_index(3, 4)], 0);
+        }
+    }
+}
+
 mod pieces_masks {
     use super::*;
 
@@ -195,11 +274,6 @@ mod pieces_masks {
         pub pawn_moves: ByColor<BySquare>,
         pub pawn_double_moves: ByColor<BySquare>,
         pub en_passant_attacks: ByColor<BySquare>,
-        pub castling_moves: ByColor<[BySquare; CASTLING_AVAILABILITY_SIZE]>,
-        // The squares between the rook and the king
-        pub castling_in_between: ByColor<[BySquare; CASTLING_AVAILABILITY_SIZE]>,
-        pub castling_corners: ByColor<BySquare<CastlingAvailability>>,
-        pub castling_rook_moves: ByColor<[BySquare; CASTLING_AVAILABILITY_SIZE]>,
     }
 
     // Generates the attack pattern for every piece on every square on an empty board
@@ -214,10 +288,6 @@ mod pieces_masks {
         let en_passant_attacks = generate_en_passant();
         let pawn_moves  = generate_pawn_moves();
         let pawn_double_moves = generate_pawn_double_moves();
-        let castling_moves = generate_castling_moves();
-        let castling_in_between= generate_castling_in_between();
-        let castling_corners= generate_castling_corners();
-        let castling_rook_moves = generate_castling_rook_moves();
         
         let mut i = 0;
         while i < BOARD_SIZE {
@@ -235,10 +305,6 @@ mod pieces_masks {
             pawn_moves, 
             pawn_double_moves,
             en_passant_attacks,
-            castling_moves,
-            castling_in_between,
-            castling_corners,
-            castling_rook_moves
         }
     }
 
@@ -251,8 +317,8 @@ mod pieces_masks {
         
         let mut file = 0;
         while file < BOARD_FILES {
-            white[square_index(white_rank, file) as usize] = get_single_bit_board((white_rank + 1) as isize, file as isize);
-            black[square_index(black_rank, file) as usize] = get_single_bit_board((black_rank - 1) as isize, file as isize);
+            white[square_index(white_rank, file)] = get_single_bit_board((white_rank + 1) as isize, file as isize);
+            black[square_index(black_rank, file)] = get_single_bit_board((black_rank - 1) as isize, file as isize);
             file += 1;
         }
 
@@ -272,7 +338,7 @@ mod pieces_masks {
             index += 1;
         }
 
-        return [ white, black ]
+        [ white, black ]
     }
 
     const fn generate_pawn_double_moves() -> ByColor<BySquare> {
@@ -284,76 +350,14 @@ mod pieces_masks {
         
         let mut file = 0;
         while file < BOARD_FILES {
-            white[square_index(white_rank, file) as usize] = get_single_bit_board((white_rank + 2) as isize, file as isize);
-            black[square_index(black_rank, file) as usize] = get_single_bit_board((black_rank - 2) as isize, file as isize);
+            white[square_index(white_rank, file)] = get_single_bit_board((white_rank + 2) as isize, file as isize);
+            black[square_index(black_rank, file)] = get_single_bit_board((black_rank - 2) as isize, file as isize);
             file += 1;
         }
 
         [ white, black ]
     }
 
-    const fn generate_castling_moves() -> ByColor<[BySquare; CASTLING_AVAILABILITY_SIZE]> {
-        let mut white = [[0 as BitBoard; BOARD_SIZE]; CASTLING_AVAILABILITY_SIZE];
-        let mut black = [[0 as BitBoard; BOARD_SIZE]; CASTLING_AVAILABILITY_SIZE];
-
-        const WHITE_IDX: usize = square_index(0, 3);
-        white[CastlingAvailability::KingSide.bits()][WHITE_IDX] = get_single_bit_board(0, 1);
-        white[CastlingAvailability::QueenSide.bits()][WHITE_IDX] = get_single_bit_board(0, 5);
-        white[CastlingAvailability::KingSide.bits() | CastlingAvailability::QueenSide.bits()][WHITE_IDX] = get_single_bit_board(0, 1) | get_single_bit_board(0, 5);
-
-        const BLACK_IDX: usize = square_index(7, 3);
-        black[CastlingAvailability::KingSide.bits()][BLACK_IDX] = get_single_bit_board(7, 1);
-        black[CastlingAvailability::QueenSide.bits()][BLACK_IDX] = get_single_bit_board(7, 5);
-        black[CastlingAvailability::KingSide.bits() | CastlingAvailability::QueenSide.bits()][BLACK_IDX] = get_single_bit_board(7, 1) | get_single_bit_board(7, 5);
-
-        [ white, black ]
-    }
-
-    const fn generate_castling_in_between() -> ByColor<[BySquare; CASTLING_AVAILABILITY_SIZE]> {
-        let mut white = [[0 as BitBoard; BOARD_SIZE]; CASTLING_AVAILABILITY_SIZE];
-        let mut black = [[0 as BitBoard; BOARD_SIZE]; CASTLING_AVAILABILITY_SIZE];
-
-        const WHITE_IDX: usize = square_index(0, 3);
-        white[CastlingAvailability::KingSide.bits()][WHITE_IDX] = 0x0000000000000006;
-        white[CastlingAvailability::QueenSide.bits()][WHITE_IDX] = 0x0000000000000070;
-        white[CastlingAvailability::KingSide.bits() | CastlingAvailability::QueenSide.bits()][WHITE_IDX] = 0x0000000000000076;
-
-        const BLACK_IDX: usize = square_index(7, 3);
-        black[CastlingAvailability::KingSide.bits()][BLACK_IDX] = 0x0600000000000000;
-        black[CastlingAvailability::QueenSide.bits()][BLACK_IDX] = 0x7000000000000000;
-        black[CastlingAvailability::KingSide.bits() | CastlingAvailability::QueenSide.bits()][BLACK_IDX] = 0x7600000000000000;
-
-        [ white, black ]
-    }
-
-    const fn generate_castling_corners() -> ByColor<BySquare<CastlingAvailability>> {
-        let mut castling_corners = [[CastlingAvailability::None; BOARD_SIZE]; PIECE_COLOR_COUNT];
-
-        castling_corners[PieceColor::White as usize][square_index(0, 0)] = CastlingAvailability::KingSide;
-        castling_corners[PieceColor::White as usize][square_index(0, 7)] = CastlingAvailability::QueenSide;
-        castling_corners[PieceColor::Black as usize][square_index(7, 0)] = CastlingAvailability::KingSide;
-        castling_corners[PieceColor::Black as usize][square_index(7, 7)] = CastlingAvailability::QueenSide;
-
-        castling_corners
-    }
-
-    const fn generate_castling_rook_moves() -> ByColor<[BySquare; CASTLING_AVAILABILITY_SIZE]> {
-        let mut white = [[0 as BitBoard; BOARD_SIZE]; CASTLING_AVAILABILITY_SIZE];
-        let mut black = [[0 as BitBoard; BOARD_SIZE]; CASTLING_AVAILABILITY_SIZE];
-
-        white[CastlingAvailability::KingSide.bits()][square_index(0, 1)] = 0x0000000000000005;
-        white[CastlingAvailability::QueenSide.bits()][square_index(0, 5)] = 0x0000000000000090;
-        white[CastlingAvailability::KingSide.bits() | CastlingAvailability::QueenSide.bits()][square_index(0, 1)] = 0x0000000000000005;
-        white[CastlingAvailability::KingSide.bits() | CastlingAvailability::QueenSide.bits()][square_index(0, 5)] = 0x0000000000000090;
-
-        black[CastlingAvailability::KingSide.bits()][square_index(7, 1)] = 0x0500000000000000;
-        black[CastlingAvailability::QueenSide.bits()][square_index(7, 5)] = 0x9000000000000000;
-        black[CastlingAvailability::KingSide.bits() | CastlingAvailability::QueenSide.bits()][square_index(7, 1)] = 0x0500000000000000;
-        black[CastlingAvailability::KingSide.bits() | CastlingAvailability::QueenSide.bits()][square_index(7, 5)] = 0x9000000000000000;
-
-        [ white, black ]
-    }
-
     const fn generate_attacks_knight() -> [BitBoard; BOARD_SIZE] {
         let mut result: [BitBoard; BOARD_SIZE] = [0; BOARD_SIZE];
 
@@ -415,7 +419,7 @@ mod pieces_masks {
             index += 1;
         }
 
-        return (white, black)
+        (white, black)
     }
 
     #[cfg(test)]
@@ -464,15 +468,188 @@ mod pieces_masks {
             assert_eq!(MOVES[PieceColor::Black as usize][square_index(6, 4)], get_single_bit_board(4, 4));
         }
 
-        #[test]
-        fn test_castle_moves_generation() {
-            const MOVES: &[[[u64; BOARD_SIZE]; CASTLING_AVAILABILITY_SIZE]; PIECE_COLOR_COUNT] = &PIECES.castling_moves;
-            assert_eq!(MOVES[PieceColor::White as usize][CastlingAvailability::KingSide.bits()][square_index(3, 4)], 0);
-            assert_eq!(MOVES[PieceColor::White as usize][CastlingAvailability::KingSide.bits()][square_index(0, 3)], 0x2);
-            assert_eq!(MOVES[PieceColor::White as usize][CastlingAvailability::KingSide.bits()][square_index(1, 3)], 0);
-            assert_eq!(MOVES[PieceColor::White as usize][CastlingAvailability::QueenSide.bits()][square_index(0, 2)], 0);
-            assert_eq!(MOVES[PieceColor::Black as usize][CastlingAvailability::KingSide.bits() | CastlingAvailability::QueenSide.bits()][square_index(7, 3)], 0x2200000000000000);
-            assert_eq!(MOVES[PieceColor::Black as usize][CastlingAvailability::QueenSide.bits()][square_index(7, 3)], 0x2000000000000000);
+    }
+}
+
+// https://www.chessprogramming.org/Magic_Bitboards
+mod magic_masks {
+    use super::*;
+
+    // The number of attack sets of all rook and bishop squares
+    const ATTACKS_SIZE: usize = 107648;
+
+    // Found with utils/magic_generation.py
+    const BISHOP_MAGICS: BySquare<u64> = [
+        0x0408308400802201, 0x8103100106118000, 0x0010041651433102, 0x0004240094000004, 0x30011040410C1008, 0x0018220820420820, 0x5284020805042012, 0x0480420210024203,
+        0x0850886101021204, 0x0062442802240020, 0x0220121086120100, 0x0004082080200008, 0x1002220210046800, 0x0020810422414800, 0x0400005F08201040, 0x0008903602101400,
+        0x8050010A02D00400, 0x101000AA82480305, 0x8008000102040011, 0x1000880802004080, 0x0009000190400002, 0x1001801901414010, 0x0821000080882000, 0x6042041900420208,
+        0x0450089004E00400, 0x0C01040048100460, 0x0640405008020046, 0x0324080100220040, 0x4112001102005000, 0x9008004108090800, 0x1401020021080101, 0x058400200105210A,
+        0x2050082208041C84, 0x0084014800043001, 0x0200209000080020, 0x0006820080080080, 0x0409020400020500, 0x00105A0200002082, 0x2002120049040400, 0x000A088820030408,
+        0x000488541000C009, 0x04284A0211182008, 0x0002001044240800, 0x8000112015020807, 0x488C020204109202, 0x0082100502020109, 0x4410010104210101, 0x0008080910400020,
+        0x2020880808842280, 0x082A004A02308900, 0x0054010088040000, 0x0002490042022420, 0x0812004008220000, 0x0110102021010104, 0x00D0451004024000, 0x0CC4011204010A22,
+        0x1409840100822110, 0x0031805200900804, 0x0340400232051004, 0x030C008818842402, 0x0300000008208844, 0x80000C0810019203, 0x00A0910441080200, 0x92C0100102008010,
+    ];
+    const ROOK_MAGICS: BySquare<u64> = [
+        0x8D80004004302480, 0x0440001000402000, 0x3480200289100080, 0x0480100208008004, 0x0280080180040002, 0x0600100600040831, 0x0400300401084082, 0x1A00020040810024,
+        0x0082002080420101, 0x0202002080410200, 0x0210801000200882, 0x2408801000080080, 0x5090800800840080, 0x0222000488908200, 0x0004001002080104, 0x0C20800080005900,
+        0x924380800820C011, 0x0040484010002000, 0x0020008020801000, 0x1020808010000804, 0x0402850008009100, 0x8054008002008004, 0x400004005F100802, 0x00C65A0004164A81,
+        0x0C00408200210200, 0x041002C240002000, 0x0020004100210010, 0x0600100080080082, 0xC208008880040080, 0x0400020080040080, 0xE000420400614810, 0x0020008200104104,
+        0x0800804000800038, 0x0290002008400048, 0x2080200282801000, 0x0C1600100A004120, 0xC100800800800402, 0x04A0020080800400, 0x0208480184000210, 0x1801010082000044,
+        0x1000400080008024, 0x100120100040C000, 0xA025002002450010, 0xC240080010008080, 0x842B010801050010, 0x0080040002008080, 0x0040821001840008, 0x0000412040920004,
+        0x0421400680002480, 0x0100400080200080, 0x0018801042002200, 0x0800480080100280, 0x0685800402080080, 0x0089008400020900, 0x5044302802018400, 0x0200005084110200,
+        0x0020310080012441, 0x0000204104120086, 0x00004010800A2202, 0x2002082010000501, 0x0002006010440882, 0x8002004150381402, 0x050004A502181004, 0xC200002081004402,
+    ];
+
+    const BISHOP_DIRS: [Dir; 4] = [ Dir::NorthEast, Dir::SouthEast, Dir::SouthWest, Dir::NorthWest ];
+    const ROOK_DIRS: [Dir; 4] = [ Dir::North, Dir::East, Dir::South, Dir::West ];
+
+    #[derive(Copy, Clone)]
+    pub struct Magic {
+        pub mask: BitBoard,     // The squares which can block the piece, the edges of the board are never relevant
+        pub magic: u64,
+        pub shift: u32,
+        pub offset: usize,      // Where the attacks of the square start in the attack table
+    }
+
+    impl Magic {
+        // Returns the index of the attack set in the attack table
+        #[cfg(not(feature = "pext"))]
+        #[inline(always)]
+        pub const fn index(&self, occupied: BitBoard) -> usize {
+            self.offset + ((occupied & self.mask).wrapping_mul(self.magic) >> self.shift) as usize
+        }
+
+        // Returns the index of the attack set in the attack table
+        #[cfg(feature = "pext")]
+        #[inline(always)]
+        pub fn index(&self, occupied: BitBoard) -> usize {
+            self.offset + pext(occupied, self.mask) as usize
         }
     }
-}
\ No newline at end of file
+
+    pub struct Magics {
+        pub bishops: BySquare<Magic>,
+        pub rooks: BySquare<Magic>,
+        pub attacks: [BitBoard; ATTACKS_SIZE],
+    }
+
+    impl Magics {
+        // Returns the squares a bishop attacks, including the blocking pieces
+        #[inline(always)]
+        pub fn bishop_attacks(&self, square: usize, occupied: BitBoard) -> BitBoard {
+            self.attacks[self.bishops[square].index(occupied)]
+        }
+
+        // Returns the squares a rook attacks, including the blocking pieces
+        #[inline(always)]
+        pub fn rook_attacks(&self, square: usize, occupied: BitBoard) -> BitBoard {
+            self.attacks[self.rooks[square].index(occupied)]
+        }
+    }
+
+    #[cfg(all(feature = "pext", target_arch = "x86_64", target_feature = "bmi2"))]
+    #[inline(always)]
+    fn pext(value: u64, mask: u64) -> u64 {
+        // SAFETY: The target feature is enabled at compile time
+        unsafe { std::arch::x86_64::_pext_u64(value, mask) }
+    }
+
+    // Gathers the bits of value selected by mask into the lowest bits of the result
+    #[cfg(all(feature = "pext", not(all(target_arch = "x86_64", target_feature = "bmi2"))))]
+    #[inline(always)]
+    fn pext(value: u64, mut mask: u64) -> u64 {
+        let mut result = 0;
+        let mut bit = 1;
+        while mask != 0 {
+            if value & mask & mask.wrapping_neg() != 0 {
+                result |= bit;
+            }
+            bit <<= 1;
+            mask &= mask - 1;
+        }
+        result
+    }
+
+    // Directions going towards higher square indices
+    const fn is_positive(dir: Dir) -> bool {
+        matches!(dir, Dir::North | Dir::NorthEast | Dir::West | Dir::NorthWest)
+    }
+
+    // Returns the square on the ray closest to its origin
+    const fn nearest_square(ray: BitBoard, dir: Dir) -> usize {
+        if is_positive(dir) { ray.trailing_zeros() as usize } else { 63 - ray.leading_zeros() as usize }
+    }
+
+    // https://www.chessprogramming.org/Classical_Approach
+    const fn sliding_attacks(rays: &rays_masks::Rays, square: usize, occupied: BitBoard, dirs: &[Dir; 4]) -> BitBoard {
+        let mut attacks: BitBoard = 0;
+        let mut i = 0;
+        while i < dirs.len() {
+            let dir = dirs[i];
+            let ray = rays[square][dir as usize];
+            let blockers = ray & occupied;
+            attacks |= if blockers == 0 { ray } else { ray & !rays[nearest_square(blockers, dir)][dir as usize] };
+            i += 1;
+        }
+        attacks
+    }
+
+    const fn relevant_mask(rays: &rays_masks::Rays, square: usize, dirs: &[Dir; 4]) -> BitBoard {
+        let mut mask: BitBoard = 0;
+        let mut i = 0;
+        while i < dirs.len() {
+            let ray = rays[square][dirs[i] as usize];
+            // The farthest square of the ray is the nearest square when looking in the opposite direction
+            if ray != 0 {
+                mask |= ray & !((1 as BitBoard) << nearest_square(ray, Dir::opposite(dirs[i])));
+            }
+            i += 1;
+        }
+        mask
+    }
+
+    // Fills in the attacks of every subset of the relevant squares, using the Carry-Rippler trick
+    // Returns the offset of the next square
+    const fn fill_attacks(rays: &rays_masks::Rays, attacks: &mut [BitBoard; ATTACKS_SIZE], magic: &Magic, square: usize, dirs: &[Dir; 4]) -> usize {
+        let mut occupied: BitBoard = 0;
+        let mut subset: usize = 0;
+        loop {
+            // The subsets are enumerated in the order of their PEXT index
+            let index = if cfg!(feature = "pext") {
+                magic.offset + subset
+            } else {
+                magic.offset + ((occupied & magic.mask).wrapping_mul(magic.magic) >> magic.shift) as usize
+            };
+            attacks[index] = sliding_attacks(rays, square, occupied, dirs);
+            subset += 1;
+            occupied = occupied.wrapping_sub(magic.mask) & magic.mask;
+            if occupied == 0 {
+                break;
+            }
+        }
+        magic.offset + (1 << magic.mask.count_ones())
+    }
+
+    pub const fn generate(rays: &rays_masks::Rays) -> Magics {
+        const EMPTY: Magic = Magic { mask: 0, magic: 0, shift: 0, offset: 0 };
+        let mut bishops = [EMPTY; BOARD_SIZE];
+        let mut rooks = [EMPTY; BOARD_SIZE];
+        let mut attacks = [0; ATTACKS_SIZE];
+
+        let mut offset = 0;
+        let mut square = 0;
+        while square < BOARD_SIZE {
+            let mask = relevant_mask(rays, square, &BISHOP_DIRS);
+            bishops[square] = Magic { mask, magic: BISHOP_MAGICS[square], shift: 64 - mask.count_ones(), offset };
+            offset = fill_attacks(rays, &mut attacks, &bishops[square], square, &BISHOP_DIRS);
+
+            let mask = relevant_mask(rays, square, &ROOK_DIRS);
+            rooks[square] = Magic { mask, magic: ROOK_MAGICS[square], shift: 64 - mask.count_ones(), offset };
+            offset = fill_attacks(rays, &mut attacks, &rooks[square], square, &ROOK_DIRS);
+            square += 1;
+        }
+        assert!(offset == ATTACKS_SIZE);
+
+        Magics { bishops, rooks, attacks }
+    }
+}
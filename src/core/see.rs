@@ -0,0 +1,64 @@
+// https://www.chessprogramming.org/SEE_-_The_Swap_Algorithm
+use crate::core::board::*;
+use crate::core::chess_board::*;
+use crate::core::move_generation::*;
+use crate::core::piece::*;
+
+/// The values of the pieces in centipawns used by the static exchange evaluation
+/// The king is never captured, so it is worth nothing
+pub const SEE_VALUES: ByPiece<i32> = [ 100, 300, 300, 500, 900, 0 ];
+
+const ORDERED_PIECE_TYPES: [PieceType; 6] = [ PieceType::Pawn, PieceType::Knight, PieceType::Bishop, PieceType::Rook, PieceType::Queen, PieceType::King ];
+
+// Returns the material the current player wins by moving "piece_type" from "bb_src" to "dst", capturing "captured"
+// "bb_src" is empty for a drop and "bb_captured" is the square of the captured piece, which differs from "dst" for en passant
+// Both players then capture on "dst" with their least valuable piece, as long as capturing doesn't lose material
+// Attackers behind the pieces which have captured are uncovered, but pins are ignored
+pub fn static_exchange(chess_board: &ChessBoard, bb_src: BitBoard, dst: usize, piece_type: PieceType, captured: Option<(PieceType, BitBoard)>) -> i32 {
+    let bb_dst = (1 as BitBoard) << dst;
+    let mut gain = [0; 32];
+    gain[0] = captured.map_or(0, |(captured_type, _)| SEE_VALUES[captured_type as usize]);
+    // A promotion wins the difference between the pawn and the new piece
+    let moving_pawn = chess_board.pieces[PieceType::Pawn as usize] & bb_src != 0;
+    if moving_pawn && piece_type != PieceType::Pawn {
+        gain[0] += SEE_VALUES[piece_type as usize] - SEE_VALUES[PieceType::Pawn as usize];
+    }
+
+    let bb_captured = captured.map_or(0, |(_, bb_captured)| bb_captured);
+    let mut occupied = (chess_board.all_pieces() & !bb_src & !bb_captured) | bb_dst;
+
+    let mut piece_on_square = piece_type;
+    let mut side = PieceColor::opposite(chess_board.current_color);
+    let mut depth = 0;
+    while depth + 1 < gain.len() {
+        let attackers = get_pieces_attacking_square(chess_board, dst, side, occupied) & occupied & !bb_dst;
+        let Some((attacker_type, bb_attacker)) = least_valuable(chess_board, attackers) else {
+            break;
+        };
+        // The king may only capture if the opponent can't capture it back
+        if attacker_type == PieceType::King
+                && get_pieces_attacking_square(chess_board, dst, PieceColor::opposite(side), occupied & !bb_attacker) & occupied & !bb_dst != 0 {
+            break;
+        }
+
+        depth += 1;
+        gain[depth] = SEE_VALUES[piece_on_square as usize] - gain[depth - 1];
+        occupied &= !bb_attacker;
+        piece_on_square = attacker_type;
+        side = PieceColor::opposite(side);
+    }
+
+    // Every player may stop capturing when it would lose material
+    while depth > 0 {
+        gain[depth - 1] = -(-gain[depth - 1]).max(gain[depth]);
+        depth -= 1;
+    }
+    gain[0]
+}
+
+fn least_valuable(chess_board: &ChessBoard, attackers: BitBoard) -> Option<(PieceType, BitBoard)> {
+    ORDERED_PIECE_TYPES.into_iter().find_map(|piece_type| {
+        let pieces = attackers & chess_board.pieces[piece_type as usize];
+        (pieces != 0).then(|| (piece_type, pieces & pieces.wrapping_neg()))
+    })
+}
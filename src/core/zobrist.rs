@@ -0,0 +1,246 @@
+use super::board::*;
+use super::chess_board::*;
+use super::piece::*;
+use super::precompute_masks::*;
+
+pub const ZOBRIST: Zobrist = Zobrist::new();
+
+// https://www.chessprogramming.org/Zobrist_Hashing
+// Contains one random key for every part of the position which is used to hash it
+pub struct Zobrist {
+    pub pieces: ByColor<ByPiece<BySquare<u64>>>,
+    pub castling: ByColor<BySide<[u64; BOARD_FILES]>>,
+    pub en_passant: [u64; BOARD_FILES],
+    pub black_to_move: u64,
+    pub checks: ByColor<[u64; Zobrist::MAX_CHECKS]>,
+    pub pockets: ByColor<ByPiece<[u64; Zobrist::POCKET_COUNTS]>>,
+}
+
+impl Zobrist {
+    const SEED: u64 = 0x5EED_C0DE_CAFE_F00D;
+    // Counting checks past the third is never needed, Three-check ends with it
+    const MAX_CHECKS: usize = 4;
+    // Every count from an empty pocket up to Pocket::MAX_COUNT has its own key
+    const POCKET_COUNTS: usize = Pocket::MAX_COUNT as usize + 1;
+
+    const fn new() -> Self {
+        let mut state = Zobrist::SEED;
+
+        let mut pieces = [[[0; BOARD_SIZE]; PIECE_TYPE_COUNT]; PIECE_COLOR_COUNT];
+        let mut color = 0;
+        while color < PIECE_COLOR_COUNT {
+            let mut piece_type = 0;
+            while piece_type < PIECE_TYPE_COUNT {
+                let mut square = 0;
+                while square < BOARD_SIZE {
+                    pieces[color][piece_type][square] = next_key(&mut state);
+                    square += 1;
+                }
+                piece_type += 1;
+            }
+            color += 1;
+        }
+
+        // Castling keys are indexed by the file of the castling rook, so the same right with another rook is another position
+        let mut castling = [[[0; BOARD_FILES]; CASTLING_SIDE_COUNT]; PIECE_COLOR_COUNT];
+        let mut color = 0;
+        while color < PIECE_COLOR_COUNT {
+            let mut side = 0;
+            while side < CASTLING_SIDE_COUNT {
+                let mut file = 0;
+                while file < BOARD_FILES {
+                    castling[color][side][file] = next_key(&mut state);
+                    file += 1;
+                }
+                side += 1;
+            }
+            color += 1;
+        }
+
+        let mut en_passant = [0; BOARD_FILES];
+        let mut file = 0;
+        while file < BOARD_FILES {
+            en_passant[file] = next_key(&mut state);
+            file += 1;
+        }
+
+        let black_to_move = next_key(&mut state);
+
+        // Zero checks have no key, so the checks don't change the hash of positions of standard chess
+        let mut checks = [[0; Zobrist::MAX_CHECKS]; PIECE_COLOR_COUNT];
+        let mut color = 0;
+        while color < PIECE_COLOR_COUNT {
+            let mut count = 1;
+            while count < Zobrist::MAX_CHECKS {
+                checks[color][count] = next_key(&mut state);
+                count += 1;
+            }
+            color += 1;
+        }
+
+        // Like the checks, an empty pocket has no key
+        let mut pockets = [[[0; Zobrist::POCKET_COUNTS]; PIECE_TYPE_COUNT]; PIECE_COLOR_COUNT];
+        let mut color = 0;
+        while color < PIECE_COLOR_COUNT {
+            let mut piece_type = 0;
+            while piece_type < PIECE_TYPE_COUNT {
+                let mut count = 1;
+                while count < Zobrist::POCKET_COUNTS {
+                    pockets[color][piece_type][count] = next_key(&mut state);
+                    count += 1;
+                }
+                piece_type += 1;
+            }
+            color += 1;
+        }
+
+        Zobrist { pieces, castling, en_passant, black_to_move, checks, pockets }
+    }
+}
+
+// https://prng.di.unimi.it/splitmix64.c
+const fn next_key(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E3779B97F4A7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+// Calculates the hash of the position from scratch
+// ChessBoard::hash returns the same hash from the key it updates with every change of the position
+pub fn hash(chess_board: &ChessBoard) -> u64 {
+    key(chess_board) ^ en_passant_key(chess_board)
+}
+
+// Calculates the hash of the position without the en passant square from scratch
+pub fn key(chess_board: &ChessBoard) -> u64 {
+    let mut key: u64 = 0;
+
+    for color in [PieceColor::White, PieceColor::Black] {
+        for piece_type in [PieceType::Pawn, PieceType::Knight, PieceType::Bishop, PieceType::Rook, PieceType::Queen, PieceType::King] {
+            let mut pieces = chess_board.pieces[piece_type as usize] & chess_board.all_pieces[color as usize];
+            while pieces != 0 {
+                let square = pop_lsb(&mut pieces);
+                key ^= ZOBRIST.pieces[color as usize][piece_type as usize][square];
+            }
+        }
+        for side in CASTLING_SIDES {
+            if let Some(file) = chess_board.castling_rook_files[color as usize][side as usize] {
+                key ^= ZOBRIST.castling[color as usize][side as usize][file];
+            }
+        }
+    }
+
+    if chess_board.current_color == PieceColor::Black {
+        key ^= ZOBRIST.black_to_move;
+    }
+
+    for color in [PieceColor::White, PieceColor::Black] {
+        key ^= check_key(color, chess_board.checks[color as usize]);
+        let pocket = &chess_board.pockets[color as usize];
+        for piece_type in [PieceType::Pawn, PieceType::Knight, PieceType::Bishop, PieceType::Rook, PieceType::Queen] {
+            key ^= ZOBRIST.pockets[color as usize][piece_type as usize][pocket.count(piece_type) as usize];
+        }
+    }
+
+    key
+}
+
+// The en passant square is only part of the position if a pawn can capture on it, so repetitions are detected
+//      when a double pawn move which can't be captured en passant leads to an earlier position
+// It depends on the side to move and the pawns next to the square, so it is not kept in the key of the position
+pub fn en_passant_key(chess_board: &ChessBoard) -> u64 {
+    let en_passant_square = chess_board.en_passant_mask.trailing_zeros() as usize;
+    let own_pawns = chess_board.pieces[PieceType::Pawn as usize] & chess_board.all_pieces[chess_board.current_color as usize];
+    if chess_board.en_passant_mask != 0
+            && BBMASKS.pieces.attacks[PieceColor::opposite(chess_board.current_color) as usize][PieceType::Pawn as usize][en_passant_square] & own_pawns != 0 {
+        return ZOBRIST.en_passant[file_index(en_passant_square)];
+    }
+    0
+}
+
+pub fn check_key(color: PieceColor, checks: u32) -> u64 {
+    ZOBRIST.checks[color as usize][(checks as usize).min(Zobrist::MAX_CHECKS - 1)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::variant::*;
+
+    #[test]
+    fn test_hash_transposition() {
+        // Nf3 Nf6 Ng1 Ng8 leads back to the start position
+        let start = ChessBoard::new("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+        let mut chess_board = start.clone();
+        for (src, dst) in [ (square_index(0, 1), square_index(2, 2)), (square_index(7, 1), square_index(5, 2)),
+                            (square_index(2, 2), square_index(0, 1)), (square_index(5, 2), square_index(7, 1)) ] {
+            chess_board.make_move(src, (1 as BitBoard) << dst);
+            chess_board.toggle_current_color();
+        }
+        assert_eq!(hash(&chess_board), hash(&start));
+    }
+
+    #[test]
+    fn test_hash_differs() {
+        let start = ChessBoard::new("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+        let black_to_move = ChessBoard::new("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1").unwrap();
+        let no_castling = ChessBoard::new("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w Kkq - 0 1").unwrap();
+        let en_passant = ChessBoard::new("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").unwrap();
+        let no_en_passant = ChessBoard::new("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").unwrap();
+        assert_ne!(hash(&start), hash(&black_to_move));
+        assert_ne!(hash(&start), hash(&no_castling));
+        assert_ne!(hash(&en_passant), hash(&no_en_passant));
+    }
+
+    #[test]
+    fn test_hash_ignores_uncapturable_en_passant() {
+        let en_passant = ChessBoard::new("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").unwrap();
+        let no_en_passant = ChessBoard::new("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").unwrap();
+        assert_eq!(hash(&en_passant), hash(&no_en_passant));
+    }
+
+    #[test]
+    fn test_hash_pocket_counts() {
+        // Every pocket size up to the most pieces of a type has its own hash
+        let mut hashes: Vec<u64> = Vec::new();
+        for count in 0..=Pocket::MAX_COUNT as usize {
+            let chess_board = ChessBoard::new(&format!("4k3/8/8/8/8/8/8/4K3[{}] w - - 0 1", "P".repeat(count))).unwrap();
+            assert!(!hashes.contains(&hash(&chess_board)));
+            hashes.push(hash(&chess_board));
+        }
+    }
+
+    // Walks the move tree and compares the hash updated by every move with the hash calculated from scratch
+    fn test_incremental_helper(chess_board: &crate::ChessBoard, depth: usize) {
+        assert_eq!(chess_board.hash(), hash(&chess_board.inner));
+        if depth == 0 {
+            return;
+        }
+        for chess_move in chess_board.legal_moves() {
+            let child = chess_board.make_move(chess_move).unwrap().0;
+            if chess_board.is_standard() {
+                assert_eq!(chess_board.make_move_unchecked(chess_move).hash(), child.hash());
+            }
+            test_incremental_helper(&child, depth - 1);
+        }
+    }
+
+    #[test]
+    fn test_hash_incremental() {
+        let positions: [(&'static dyn Variant, &str, usize); 8] = [
+            (&Standard, "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", 3),
+            (&Standard, "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1", 3),
+            (&Standard, "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 4),
+            (&Standard, "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9", 3),
+            (&Crazyhouse, "r1bqk2r/pppp1ppp/2n2n2/2b1p3/2B1P3/5N2/PPPP1PPP/RNBQK2R~[Pn] w KQkq - 0 1", 2),
+            (&Atomic, "rnbqkb1r/pppppppp/5n2/8/8/5N2/PPPPPPPP/RNBQKB1R w KQkq - 0 1", 3),
+            (&ThreeCheck, "rnbqkbnr/ppp2ppp/8/3pp3/4P3/5Q2/PPPP1PPP/RNB1KBNR w KQkq - 0 1 +1+0", 3),
+            (&Antichess, "8/1P6/8/8/8/8/6p1/8 w - - 0 1", 4),
+        ];
+        for (variant, fen, depth) in positions {
+            test_incremental_helper(&crate::ChessBoard::with_variant(variant, Some(fen)).unwrap(), depth);
+        }
+    }
+}
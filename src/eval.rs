@@ -0,0 +1,247 @@
+// https://www.chessprogramming.org/PeSTO%27s_Evaluation_Function
+use crate::chess_board::*;
+
+use crate::core::board::*;
+use crate::core::chess_board as internal;
+use crate::core::piece::*;
+
+/// The bonus of the side to move, in centipawns
+pub const TEMPO: i32 = 10;
+
+// The game phase is the sum of the phases of the pieces on the board, the start position has the maximum phase
+const PHASE: ByPiece<i32> = [ 0, 1, 1, 2, 4, 0 ];
+const MAX_PHASE: i32 = 24;
+
+const MIDDLEGAME_VALUES: ByPiece<i32> = [ 82, 337, 365, 477, 1025, 0 ];
+const ENDGAME_VALUES: ByPiece<i32> = [ 94, 281, 297, 512, 936, 0 ];
+
+// The tables are written from the view of white with a8 first, as in PeSTO
+const MIDDLEGAME_TABLES: ByPiece<BySquare<i32>> = [
+    [
+          0,   0,   0,   0,   0,   0,   0,   0,
+         98, 134,  61,  95,  68, 126,  34, -11,
+         -6,   7,  26,  31,  65,  56,  25, -20,
+        -14,  13,   6,  21,  23,  12,  17, -23,
+        -27,  -2,  -5,  12,  17,   6,  10, -25,
+        -26,  -4,  -4, -10,   3,   3,  33, -12,
+        -35,  -1, -20, -23, -15,  24,  38, -22,
+          0,   0,   0,   0,   0,   0,   0,   0,
+    ],
+    [
+       -167, -89, -34, -49,  61, -97, -15, -107,
+        -73, -41,  72,  36,  23,  62,   7,  -17,
+        -47,  60,  37,  65,  84, 129,  73,   44,
+         -9,  17,  19,  53,  37,  69,  18,   22,
+        -13,   4,  16,  13,  28,  19,  21,   -8,
+        -23,  -9,  12,  10,  19,  17,  25,  -16,
+        -29, -53, -12,  -3,  -1,  18, -14,  -19,
+       -105, -21, -58, -33, -17, -28, -19,  -23,
+    ],
+    [
+        -29,   4, -82, -37, -25, -42,   7,  -8,
+        -26,  16, -18, -13,  30,  59,  18, -47,
+        -16,  37,  43,  40,  35,  50,  37,  -2,
+         -4,   5,  19,  50,  37,  37,   7,  -2,
+         -6,  13,  13,  26,  34,  12,  10,   4,
+          0,  15,  15,  15,  14,  27,  18,  10,
+          4,  15,  16,   0,   7,  21,  33,   1,
+        -33,  -3, -14, -21, -13, -12, -39, -21,
+    ],
+    [
+         32,  42,  32,  51,  63,   9,  31,  43,
+         27,  32,  58,  62,  80,  67,  26,  44,
+         -5,  19,  26,  36,  17,  45,  61,  16,
+        -24, -11,   7,  26,  24,  35,  -8, -20,
+        -36, -26, -12,  -1,   9,  -7,   6, -23,
+        -45, -25, -16, -17,   3,   0,  -5, -33,
+        -44, -16, -20,  -9,  -1,  11,  -6, -71,
+        -19, -13,   1,  17,  16,   7, -37, -26,
+    ],
+    [
+        -28,   0,  29,  12,  59,  44,  43,  45,
+        -24, -39,  -5,   1, -16,  57,  28,  54,
+        -13, -17,   7,   8,  29,  56,  47,  57,
+        -27, -27, -16, -16,  -1,  17,  -2,   1,
+         -9, -26,  -9, -10,  -2,  -4,   3,  -3,
+        -14,   2, -11,  -2,  -5,   2,  14,   5,
+        -35,  -8,  11,   2,   8,  15,  -3,   1,
+         -1, -18,  -9,  10, -15, -25, -31, -50,
+    ],
+    [
+        -65,  23,  16, -15, -56, -34,   2,  13,
+         29,  -1, -20,  -7,  -8,  -4, -38, -29,
+         -9,  24,   2, -16, -20,   6,  22, -22,
+        -17, -20, -12, -27, -30, -25, -14, -36,
+        -49,  -1, -27, -39, -46, -44, -33, -51,
+        -14, -14, -22, -46, -44, -30, -15, -27,
+          1,   7,  -8, -64, -43, -16,   9,   8,
+        -15,  36,  12, -54,   8, -28,  24,  14,
+    ],
+];
+
+const ENDGAME_TABLES: ByPiece<BySquare<i32>> = [
+    [
+          0,   0,   0,   0,   0,   0,   0,   0,
+        178, 173, 158, 134, 147, 132, 165, 187,
+         94, 100,  85,  67,  56,  53,  82,  84,
+         32,  24,  13,   5,  -2,   4,  17,  17,
+         13,   9,  -3,  -7,  -7,  -8,   3,  -1,
+          4,   7,  -6,   1,   0,  -5,  -1,  -8,
+         13,   8,   8,  10,  13,   0,   2,  -7,
+          0,   0,   0,   0,   0,   0,   0,   0,
+    ],
+    [
+        -58, -38, -13, -28, -31, -27, -63, -99,
+        -25,  -8, -25,  -2,  -9, -25, -24, -52,
+        -24, -20,  10,   9,  -1,  -9, -19, -41,
+        -17,   3,  22,  22,  22,  11,   8, -18,
+        -18,  -6,  16,  25,  16,  17,   4, -18,
+        -23,  -3,  -1,  15,  10,  -3, -20, -22,
+        -42, -20, -10,  -5,  -2, -20, -23, -44,
+        -29, -51, -23, -15, -22, -18, -50, -64,
+    ],
+    [
+        -14, -21, -11,  -8,  -7,  -9, -17, -24,
+         -8,  -4,   7, -12,  -3, -13,  -4, -14,
+          2,  -8,   0,  -1,  -2,   6,   0,   4,
+         -3,   9,  12,   9,  14,  10,   3,   2,
+         -6,   3,  13,  19,   7,  10,  -3,  -9,
+        -12,  -3,   8,  10,  13,   3,  -7, -15,
+        -14, -18,  -7,  -1,   4,  -9, -15, -27,
+        -23,  -9, -23,  -5,  -9, -16,  -5, -17,
+    ],
+    [
+         13,  10,  18,  15,  12,  12,   8,   5,
+         11,  13,  13,  11,  -3,   3,   8,   3,
+          7,   7,   7,   5,   4,  -3,  -5,  -3,
+          4,   3,  13,   1,   2,   1,  -1,   2,
+          3,   5,   8,   4,  -5,  -6,  -8, -11,
+         -4,   0,  -5,  -1,  -7, -12,  -8, -16,
+         -6,  -6,   0,   2,  -9,  -9, -11,  -3,
+         -9,   2,   3,  -1,  -5, -13,   4, -20,
+    ],
+    [
+         -9,  22,  22,  27,  27,  19,  10,  20,
+        -17,  20,  32,  41,  58,  25,  30,   0,
+        -20,   6,   9,  49,  47,  35,  19,   9,
+          3,  22,  24,  45,  57,  40,  57,  36,
+        -18,  28,  19,  47,  31,  34,  39,  23,
+        -16, -27,  15,   6,   9,  17,  10,   5,
+        -22, -23, -30, -16, -16, -23, -36, -32,
+        -33, -28, -22, -43,  -5, -32, -20, -41,
+    ],
+    [
+        -74, -35, -18, -18, -11,  15,   4, -17,
+        -12,  17,  14,  17,  17,  38,  23,  11,
+         10,  17,  23,  15,  20,  45,  44,  13,
+         -8,  22,  24,  27,  26,  33,  26,   3,
+        -18,  -4,  21,  24,  27,  23,   9, -11,
+        -19,  -3,  11,  21,  23,  16,   7,  -9,
+        -27, -11,   4,  13,  14,   4,  -5, -17,
+        -53, -34, -21, -11, -28, -14, -24, -43,
+    ],
+];
+
+// The value of every piece on every square, including the value of the piece, indexed as the bit boards are
+// The square with index 0 is h1, which is the last square of the tables of white, black uses the tables mirrored vertically
+const MIDDLEGAME: ByColor<ByPiece<BySquare<i32>>> = combine_tables(&MIDDLEGAME_VALUES, &MIDDLEGAME_TABLES);
+const ENDGAME: ByColor<ByPiece<BySquare<i32>>> = combine_tables(&ENDGAME_VALUES, &ENDGAME_TABLES);
+
+const fn combine_tables(values: &ByPiece<i32>, tables: &ByPiece<BySquare<i32>>) -> ByColor<ByPiece<BySquare<i32>>> {
+    let mut combined = [[[0; BOARD_SIZE]; PIECE_TYPE_COUNT]; PIECE_COLOR_COUNT];
+    let mut piece_type = 0;
+    while piece_type < PIECE_TYPE_COUNT {
+        let mut square = 0;
+        while square < BOARD_SIZE {
+            let table_square = BOARD_SIZE - 1 - square;
+            combined[PieceColor::White as usize][piece_type][square] = values[piece_type] + tables[piece_type][table_square];
+            combined[PieceColor::Black as usize][piece_type][square] = values[piece_type] + tables[piece_type][table_square ^ 56];
+            square += 1;
+        }
+        piece_type += 1;
+    }
+    combined
+}
+
+/// The middlegame and endgame scores of both colors and the game phase of a position
+/// It can be updated piece by piece as moves are made, unmaking a move is restoring the copy from before it
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Evaluation {
+    middlegame: ByColor<i32>,
+    endgame: ByColor<i32>,
+    phase: i32,
+}
+
+impl Evaluation {
+    /// Returns the evaluation of the pieces on the chess board
+    pub fn new(chess_board: &ChessBoard) -> Self {
+        Evaluation::from_inner(&chess_board.inner)
+    }
+
+    pub(crate) fn from_inner(chess_board: &internal::ChessBoard) -> Self {
+        let mut evaluation = Evaluation { middlegame: [0; PIECE_COLOR_COUNT], endgame: [0; PIECE_COLOR_COUNT], phase: 0 };
+        for color in [PieceColor::White, PieceColor::Black] {
+            for piece_type in [PieceType::Pawn, PieceType::Knight, PieceType::Bishop, PieceType::Rook, PieceType::Queen, PieceType::King] {
+                let mut pieces = chess_board.pieces[piece_type as usize] & chess_board.all_pieces[color as usize];
+                while pieces != 0 {
+                    evaluation.add_piece(color, piece_type, pop_lsb(&mut pieces));
+                }
+            }
+        }
+        evaluation
+    }
+
+    /// Adds the piece on "square" to the evaluation
+    pub fn add_piece(&mut self, color: PieceColor, piece_type: PieceType, square: usize) {
+        self.middlegame[color as usize] += MIDDLEGAME[color as usize][piece_type as usize][square];
+        self.endgame[color as usize] += ENDGAME[color as usize][piece_type as usize][square];
+        self.phase += PHASE[piece_type as usize];
+    }
+
+    /// Removes the piece on "square" from the evaluation
+    pub fn remove_piece(&mut self, color: PieceColor, piece_type: PieceType, square: usize) {
+        self.middlegame[color as usize] -= MIDDLEGAME[color as usize][piece_type as usize][square];
+        self.endgame[color as usize] -= ENDGAME[color as usize][piece_type as usize][square];
+        self.phase -= PHASE[piece_type as usize];
+    }
+
+    /// Updates the evaluation of "before" to the evaluation of "after", which are usually the positions before and after a move
+    /// Only the pieces which differ are updated, so castling, en passant, promotions and the changes of variants need no special cases
+    pub fn update(&mut self, before: &ChessBoard, after: &ChessBoard) {
+        self.update_inner(&before.inner, &after.inner);
+    }
+
+    pub(crate) fn update_inner(&mut self, before: &internal::ChessBoard, after: &internal::ChessBoard) {
+        for color in [PieceColor::White, PieceColor::Black] {
+            for piece_type in [PieceType::Pawn, PieceType::Knight, PieceType::Bishop, PieceType::Rook, PieceType::Queen, PieceType::King] {
+                let pieces_before = before.pieces[piece_type as usize] & before.all_pieces[color as usize];
+                let pieces_after = after.pieces[piece_type as usize] & after.all_pieces[color as usize];
+                let mut removed = pieces_before & !pieces_after;
+                while removed != 0 {
+                    self.remove_piece(color, piece_type, pop_lsb(&mut removed));
+                }
+                let mut added = pieces_after & !pieces_before;
+                while added != 0 {
+                    self.add_piece(color, piece_type, pop_lsb(&mut added));
+                }
+            }
+        }
+    }
+
+    /// Returns the score in centipawns from the view of "color", without the bonus of the side to move
+    /// The middlegame and endgame scores are interpolated by the game phase
+    pub fn score(&self, color: PieceColor) -> i32 {
+        let opposite_color = PieceColor::opposite(color);
+        let middlegame = self.middlegame[color as usize] - self.middlegame[opposite_color as usize];
+        let endgame = self.endgame[color as usize] - self.endgame[opposite_color as usize];
+        // Promotions can raise the phase above the one of the start position
+        let phase = self.phase.min(MAX_PHASE);
+        (middlegame * phase + endgame * (MAX_PHASE - phase)) / MAX_PHASE
+    }
+}
+
+/// Returns the static evaluation of the position in centipawns from the view of the side to move
+/// The evaluation is symmetric, the position with the colors swapped and the board mirrored has the same evaluation
+pub fn evaluate(chess_board: &ChessBoard) -> i32 {
+    Evaluation::new(chess_board).score(chess_board.inner.current_color) + TEMPO
+}
@@ -0,0 +1,359 @@
+use std::fmt;
+use std::time::Duration;
+
+use crate::chess_board::*;
+use crate::clock::*;
+use crate::mv::*;
+
+/// The reason a game has ended
+/// FivefoldRepetition and SeventyFiveMoveRule end the game without any of the players claiming a draw,
+///     ThreefoldRepetition and FiftyMoveRule end it when a player claims a draw
+/// TimeoutVsInsufficientMaterial is a draw, the player ran out of time but the opponent can't checkmate
+/// Adjudication is a result decided by an arbiter
+/// VariantEnd is a result decided by the rules of the variant, for example a king reaching the center in King of the Hill
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Termination {
+    Checkmate,
+    Stalemate,
+    FivefoldRepetition,
+    SeventyFiveMoveRule,
+    ThreefoldRepetition,
+    FiftyMoveRule,
+    DrawAgreement,
+    Resignation,
+    Timeout,
+    TimeoutVsInsufficientMaterial,
+    InsufficientMaterial,
+    Abandonment,
+    Adjudication,
+    VariantEnd,
+}
+
+/// The result of a game and the reason it ended
+/// Termination is None while the game is still being played
+/// Formats as the value of the PGN [Result] tag: "1-0", "0-1", "1/2-1/2" or "*"
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct GameResult {
+    pub state: GameState,
+    pub termination: Option<Termination>,
+}
+
+impl fmt::Display for GameResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.state {
+            GameState::Win(PieceColor::White) => write!(f, "1-0"),
+            GameState::Win(PieceColor::Black) => write!(f, "0-1"),
+            GameState::Draw => write!(f, "1/2-1/2"),
+            GameState::Playing => write!(f, "*"),
+        }
+    }
+}
+
+/// A move which has been played in a game
+/// Captured is the piece type which was captured by the move, a pawn for en passant
+/// ChessBoard is the position after the move
+/// Clock is the remaining time of the player who made the move, None if the game has no running clock
+#[derive(Debug, Clone)]
+pub struct GameMove {
+    pub chess_move: ChessMove,
+    pub move_type: MoveType,
+    pub captured: Option<PieceType>,
+    pub chess_board: ChessBoard,
+    pub clock: Option<Duration>,
+    hash: u64,
+}
+
+/// Represents a game, the starting position and every move played from it
+/// The game can be navigated back and forth through its history, the current position is the one after "ply" moves
+/// Moves which have been undone are kept until a new move is made, so they can be redone
+/// A game can also be ended by the players or an arbiter, that result applies from the ply it was given at
+#[derive(Debug, Clone)]
+pub struct Game {
+    start: ChessBoard,
+    start_hash: u64,
+    moves: Vec<GameMove>,
+    ply: usize,
+    ending: Option<(usize, GameResult)>,
+    draw_offer: Option<PieceColor>,
+    clock: Option<Clock>,
+}
+
+impl Game {
+    /// The number of times a position has to occur to end the game in a draw
+    const REPETITION_LIMIT: usize = 5;
+    /// The number of half moves without a pawn move or capture which ends the game in a draw
+    const HALF_MOVE_LIMIT: u32 = 150;
+    /// The number of times a position has to occur before a player can claim a draw
+    const CLAIM_REPETITION_LIMIT: usize = 3;
+    /// The number of half moves without a pawn move or capture before a player can claim a draw
+    const CLAIM_HALF_MOVE_LIMIT: u32 = 100;
+
+    /// Returns a new game starting from the position described in the 'fen' string
+    /// If the fen string is None, it will use the initial chess position
+    /// Returns None if the given Fen string was invalid
+    pub fn new(fen: Option<&str>) -> Option<Self> {
+        ChessBoard::new(fen).map(Game::from_chess_board)
+    }
+
+    /// Returns a new game starting from the given chess board
+    pub fn from_chess_board(chess_board: ChessBoard) -> Self {
+        let start_hash = chess_board.hash();
+        Game { start: chess_board, start_hash, moves: vec![], ply: 0, ending: None, draw_offer: None, clock: None }
+    }
+
+    /// Returns the position the game started from
+    pub fn start_position(&self) -> &ChessBoard {
+        &self.start
+    }
+
+    /// Returns the current position
+    pub fn chess_board(&self) -> &ChessBoard {
+        match self.ply {
+            0 => &self.start,
+            ply => &self.moves[ply - 1].chess_board,
+        }
+    }
+
+    /// Returns every recorded move, including the moves which have been undone
+    pub fn moves(&self) -> &[GameMove] {
+        &self.moves
+    }
+
+    /// Returns the number of moves played to reach the current position
+    pub fn ply(&self) -> usize {
+        self.ply
+    }
+
+    /// Performs the move on the current position, discarding every move which has been undone
+    /// A draw offer made by the opponent of the moving player is declined by the move
+    /// If the clock of the moving player is running it is pressed, if the player has run out of time the game ends instead
+    /// Returns None if the move is not legal or if the game has ended
+    pub fn make_move(&mut self, chess_move: ChessMove) -> Option<MoveType> {
+        if self.check_flag() || self.result().state != GameState::Playing {
+            return None;
+        }
+
+        let chess_board = self.chess_board();
+        let color = chess_board.inner.current_color;
+        let captured = chess_board.captured_piece(chess_move);
+        let (chess_board, move_type) = chess_board.make_move(chess_move)?;
+        let hash = chess_board.hash();
+        let clock = match &mut self.clock {
+            Some(clock) if clock.running() == Some(color) => clock.press(),
+            _ => None,
+        };
+
+        self.moves.truncate(self.ply);
+        self.moves.push(GameMove { chess_move, move_type, captured, chess_board, clock, hash });
+        self.ply += 1;
+        self.ending = None;
+        if self.draw_offer == Some(PieceColor::opposite(color)) {
+            self.draw_offer = None;
+        }
+        Some(move_type)
+    }
+
+    /// Takes back the last move
+    /// Returns false if there is no move to take back
+    pub fn undo(&mut self) -> bool {
+        self.go_to(self.ply.wrapping_sub(1))
+    }
+
+    /// Plays the last move which was taken back
+    /// Returns false if there is no move to play
+    pub fn redo(&mut self) -> bool {
+        self.go_to(self.ply + 1)
+    }
+
+    /// Makes the position after "ply" moves the current position, withdrawing any draw offer
+    /// Returns false if less than "ply" moves have been recorded
+    pub fn go_to(&mut self, ply: usize) -> bool {
+        if ply > self.moves.len() {
+            return false;
+        }
+        self.ply = ply;
+        self.draw_offer = None;
+        true
+    }
+
+    /// Returns the number of times the current position has occurred, including the current position
+    /// Positions are the same if the same player is to move, the pieces are on the same squares and the same moves are possible
+    pub fn repetition_count(&self) -> usize {
+        let half_moves = self.chess_board().inner.half_moves as usize;
+        let hash = self.position_hash(self.ply);
+        // Positions before the last pawn move or capture can't be repeated, and the player to move has to be the same
+        (self.ply.saturating_sub(half_moves)..=self.ply)
+            .rev()
+            .step_by(2)
+            .filter(|ply| self.position_hash(*ply) == hash)
+            .count()
+    }
+
+    /// Returns the hashes of the positions before the current one, oldest first
+    /// A search is given them to recognize repetitions of the positions of the game
+    pub fn history(&self) -> Vec<u64> {
+        (0..self.ply).map(|ply| self.position_hash(ply)).collect()
+    }
+
+    /// Returns the number of half moves since the last pawn move or capture
+    pub fn half_moves(&self) -> u32 {
+        self.chess_board().inner.half_moves
+    }
+
+    /// Returns the result of the game at the current position
+    pub fn result(&self) -> GameResult {
+        if let Some((ply, result)) = self.ending && self.ply >= ply {
+            return result;
+        }
+
+        let info = self.chess_board().info();
+        let variant_end = self.chess_board().variant().outcome(self.chess_board()).is_some();
+        let termination = match info.game_state {
+            _ if variant_end => Some(Termination::VariantEnd),
+            GameState::Win(_) => Some(Termination::Checkmate),
+            GameState::Draw => Some(Termination::Stalemate),
+            GameState::Playing if self.repetition_count() >= Game::REPETITION_LIMIT => Some(Termination::FivefoldRepetition),
+            GameState::Playing if self.half_moves() >= Game::HALF_MOVE_LIMIT => Some(Termination::SeventyFiveMoveRule),
+            GameState::Playing if !self.chess_board().has_mating_material(PieceColor::White)
+                                    && !self.chess_board().has_mating_material(PieceColor::Black) => Some(Termination::InsufficientMaterial),
+            GameState::Playing => None,
+        };
+        let state = match termination {
+            Some(Termination::FivefoldRepetition | Termination::SeventyFiveMoveRule | Termination::InsufficientMaterial) => GameState::Draw,
+            _ => info.game_state,
+        };
+        GameResult { state, termination }
+    }
+
+    /// Returns the player whose draw offer is standing, if any
+    pub fn draw_offer(&self) -> Option<PieceColor> {
+        self.draw_offer
+    }
+
+    /// Offers a draw to the opponent of "color", the offer stands until the opponent makes a move
+    /// Returns false if the game has ended or if the opponent has already offered a draw, the draw should be accepted instead
+    pub fn offer_draw(&mut self, color: PieceColor) -> bool {
+        if self.result().state != GameState::Playing || self.draw_offer == Some(PieceColor::opposite(color)) {
+            return false;
+        }
+        self.draw_offer = Some(color);
+        true
+    }
+
+    /// Accepts the draw offered by the opponent of "color", ending the game in a draw
+    /// Returns false if the opponent has not offered a draw
+    pub fn accept_draw(&mut self, color: PieceColor) -> bool {
+        if self.draw_offer != Some(PieceColor::opposite(color)) {
+            return false;
+        }
+        self.end(GameState::Draw, Termination::DrawAgreement)
+    }
+
+    /// Declines the draw offered by the opponent of "color"
+    /// Returns false if the opponent has not offered a draw
+    pub fn decline_draw(&mut self, color: PieceColor) -> bool {
+        if self.draw_offer != Some(PieceColor::opposite(color)) {
+            return false;
+        }
+        self.draw_offer = None;
+        true
+    }
+
+    /// Returns the reason a player can claim a draw in the current position, if any
+    pub fn claimable_draw(&self) -> Option<Termination> {
+        if self.result().state != GameState::Playing {
+            None
+        } else if self.repetition_count() >= Game::CLAIM_REPETITION_LIMIT {
+            Some(Termination::ThreefoldRepetition)
+        } else if self.half_moves() >= Game::CLAIM_HALF_MOVE_LIMIT {
+            Some(Termination::FiftyMoveRule)
+        } else {
+            None
+        }
+    }
+
+    /// Ends the game in a draw because of threefold repetition or the fifty-move rule
+    /// Returns false if neither applies to the current position
+    pub fn claim_draw(&mut self) -> bool {
+        match self.claimable_draw() {
+            Some(termination) => self.end(GameState::Draw, termination),
+            None => false,
+        }
+    }
+
+    /// Ends the game with a loss for "color" because the player resigned
+    /// Returns false if the game has already ended
+    pub fn resign(&mut self, color: PieceColor) -> bool {
+        self.end(GameState::Win(PieceColor::opposite(color)), Termination::Resignation)
+    }
+
+    /// Ends the game with a loss for "color" because the player ran out of time
+    /// The game is a draw instead if the opponent can't checkmate
+    /// Returns false if the game has already ended
+    pub fn time_out(&mut self, color: PieceColor) -> bool {
+        let opponent = PieceColor::opposite(color);
+        if self.chess_board().has_mating_material(opponent) {
+            self.end(GameState::Win(opponent), Termination::Timeout)
+        } else {
+            self.end(GameState::Draw, Termination::TimeoutVsInsufficientMaterial)
+        }
+    }
+
+    /// Returns the clock of the game, if any
+    pub fn clock(&self) -> Option<&Clock> {
+        self.clock.as_ref()
+    }
+
+    /// Returns the clock of the game, if any, so it can be started or stopped
+    pub fn clock_mut(&mut self) -> Option<&mut Clock> {
+        self.clock.as_mut()
+    }
+
+    /// Sets the clock which is pressed by every move, the clock has to be started for the player to move
+    pub fn set_clock(&mut self, clock: Clock) {
+        self.clock = Some(clock);
+    }
+
+    /// Ends the game if a player has run out of time
+    /// Returns true if the game was ended
+    pub fn check_flag(&mut self) -> bool {
+        match self.clock.as_ref().and_then(Clock::flagged) {
+            Some(color) => self.time_out(color),
+            None => false,
+        }
+    }
+
+    /// Ends the game with a loss for "color" because the player abandoned the game
+    /// Returns false if the game has already ended
+    pub fn abandon(&mut self, color: PieceColor) -> bool {
+        self.end(GameState::Win(PieceColor::opposite(color)), Termination::Abandonment)
+    }
+
+    /// Ends the game with the result decided by an arbiter
+    /// Returns false if the game has already ended or if the state is Playing
+    pub fn adjudicate(&mut self, state: GameState) -> bool {
+        if state == GameState::Playing {
+            return false;
+        }
+        self.end(state, Termination::Adjudication)
+    }
+
+    fn end(&mut self, state: GameState, termination: Termination) -> bool {
+        if self.result().state != GameState::Playing {
+            return false;
+        }
+        self.ending = Some((self.ply, GameResult { state, termination: Some(termination) }));
+        self.draw_offer = None;
+        if let Some(clock) = &mut self.clock {
+            clock.stop();
+        }
+        true
+    }
+
+    fn position_hash(&self, ply: usize) -> u64 {
+        match ply {
+            0 => self.start_hash,
+            ply => self.moves[ply - 1].hash,
+        }
+    }
+}
@@ -5,9 +5,27 @@ pub mod square;
 pub mod types;
 pub mod mv;
 pub mod promotion;
+pub mod perft;
+pub mod game;
+pub mod clock;
+pub mod variant;
+pub mod start_position;
+pub mod eval;
+pub mod search;
+pub mod move_picker;
+pub mod transposition;
 
 pub use chess_board::*;
 pub use square::*;
 pub use types::*;
 pub use mv::*;
-pub use promotion::*;
\ No newline at end of file
+pub use promotion::*;
+pub use perft::*;
+pub use game::*;
+pub use clock::*;
+pub use variant::*;
+pub use start_position::*;
+pub use eval::*;
+pub use search::*;
+pub use move_picker::*;
+pub use transposition::*;
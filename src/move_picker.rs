@@ -0,0 +1,266 @@
+// https://www.chessprogramming.org/Move_Ordering
+use crate::chess_board::*;
+use crate::mv::*;
+use crate::search::*;
+
+use crate::core::piece::*;
+
+/// The largest absolute score of the history table
+pub const MAX_HISTORY: i32 = 16384;
+
+// Values of the pieces for ordering captures, most valuable victim first and least valuable attacker second
+const ORDER_VALUES: ByPiece<i32> = [ 1, 3, 3, 5, 9, 10 ];
+
+/// The two most recent quiet moves which caused a beta cutoff at a ply, most recent first
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct Killers {
+    moves: [Option<ChessMove>; 2],
+}
+
+impl Killers {
+    /// Returns no killer moves
+    pub fn new() -> Self {
+        Killers::default()
+    }
+
+    /// Makes "chess_move" the most recent killer move
+    pub fn add(&mut self, chess_move: ChessMove) {
+        if self.moves[0] != Some(chess_move) {
+            self.moves[1] = self.moves[0];
+            self.moves[0] = Some(chess_move);
+        }
+    }
+
+    /// Returns the killer moves, most recent first
+    pub fn moves(&self) -> [Option<ChessMove>; 2] {
+        self.moves
+    }
+}
+
+/// Scores of quiet moves by their source and destination for every color, raised by the moves which cause beta cutoffs
+/// https://www.chessprogramming.org/History_Heuristic
+#[derive(Debug, Clone)]
+pub struct HistoryTable {
+    scores: Vec<i32>,
+}
+
+impl Default for HistoryTable {
+    fn default() -> Self {
+        HistoryTable::new()
+    }
+}
+
+impl HistoryTable {
+    /// Returns a table with every score 0
+    pub fn new() -> Self {
+        HistoryTable { scores: vec![0; PIECE_COLOR_COUNT * 64 * 64] }
+    }
+
+    /// Returns the score of the move by "color"
+    pub fn get(&self, color: PieceColor, chess_move: ChessMove) -> i32 {
+        self.scores[HistoryTable::index(color, chess_move)]
+    }
+
+    /// Adds "bonus" to the score of the move, which may be negative
+    /// The more extreme the score the less it changes, so it stays within MAX_HISTORY
+    pub fn update(&mut self, color: PieceColor, chess_move: ChessMove, bonus: i32) {
+        let bonus = bonus.clamp(-MAX_HISTORY, MAX_HISTORY);
+        let score = &mut self.scores[HistoryTable::index(color, chess_move)];
+        *score += bonus - *score * bonus.abs() / MAX_HISTORY;
+    }
+
+    /// Sets every score to 0
+    pub fn clear(&mut self) {
+        self.scores.fill(0);
+    }
+
+    fn index(color: PieceColor, chess_move: ChessMove) -> usize {
+        (color as usize * 64 + chess_move.src.get()) * 64 + chess_move.dst.get()
+    }
+}
+
+/// The quiet move which refuted a move last, by the source and destination of the move it replies to
+/// https://www.chessprogramming.org/Countermove_Heuristic
+#[derive(Debug, Clone)]
+pub struct CountermoveTable {
+    moves: Vec<Option<ChessMove>>,
+}
+
+impl Default for CountermoveTable {
+    fn default() -> Self {
+        CountermoveTable::new()
+    }
+}
+
+impl CountermoveTable {
+    /// Returns a table without countermoves
+    pub fn new() -> Self {
+        CountermoveTable { moves: vec![None; 64 * 64] }
+    }
+
+    /// Returns the countermove of "previous"
+    pub fn get(&self, previous: ChessMove) -> Option<ChessMove> {
+        self.moves[CountermoveTable::index(previous)]
+    }
+
+    /// Makes "chess_move" the countermove of "previous"
+    pub fn set(&mut self, previous: ChessMove, chess_move: ChessMove) {
+        self.moves[CountermoveTable::index(previous)] = Some(chess_move);
+    }
+
+    /// Removes every countermove
+    pub fn clear(&mut self) {
+        self.moves.fill(None);
+    }
+
+    fn index(previous: ChessMove) -> usize {
+        previous.src.get() * 64 + previous.dst.get()
+    }
+}
+
+// The stages of the move picker, in the order the moves are returned
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Stage {
+    TranspositionMove,
+    GoodCaptures,
+    Killers,
+    Countermove,
+    Quiets,
+    BadCaptures,
+    Done,
+}
+
+/// Returns the legal moves of a position ordered for alpha-beta search, with the moves most likely to cause a cutoff first
+/// The transposition table move comes first, then captures and promotions which don't lose material by most valuable victim and least valuable attacker,
+///     then the killer moves, then the countermove, then the other quiet moves by their history score
+///     and last the captures which lose material by the static exchange evaluation
+/// Every legal move is returned exactly once, moves which aren't legal are never returned
+pub struct MovePicker<'a> {
+    chess_board: &'a ChessBoard,
+    stage: Stage,
+    tt_move: Option<ChessMove>,
+    killers: Killers,
+    killer_index: usize,
+    countermove: Option<ChessMove>,
+    history: Option<&'a HistoryTable>,
+    captures: Vec<(ChessMove, i32)>,
+    bad_captures: Vec<ChessMove>,
+    quiets: Vec<ChessMove>,
+    scored_quiets: Vec<(ChessMove, i32)>,
+}
+
+impl<'a> MovePicker<'a> {
+    /// Returns a picker of every legal move
+    pub fn new(chess_board: &'a ChessBoard, tt_move: Option<ChessMove>, killers: Killers, countermove: Option<ChessMove>, history: &'a HistoryTable) -> Self {
+        MovePicker::with_moves(chess_board, legal_moves(chess_board), tt_move, killers, countermove, Some(history))
+    }
+
+    /// Returns a picker of only the captures and promotions, as searched by a quiescence search
+    pub fn captures(chess_board: &'a ChessBoard, tt_move: Option<ChessMove>) -> Self {
+        let mut moves = legal_moves(chess_board);
+        moves.retain(|chess_move| is_tactical(chess_board, *chess_move));
+        MovePicker::with_moves(chess_board, moves, tt_move, Killers::new(), None, None)
+    }
+
+    fn with_moves(chess_board: &'a ChessBoard, moves: Vec<ChessMove>, tt_move: Option<ChessMove>, killers: Killers, countermove: Option<ChessMove>, history: Option<&'a HistoryTable>) -> Self {
+        let tt_move = tt_move.filter(|tt_move| moves.contains(tt_move));
+        let (captures, quiets): (Vec<ChessMove>, Vec<ChessMove>) = moves.into_iter()
+            .filter(|chess_move| Some(*chess_move) != tt_move)
+            .partition(|chess_move| is_tactical(chess_board, *chess_move));
+        let captures = captures.into_iter().map(|chess_move| (chess_move, mvv_lva(chess_board, chess_move))).collect();
+        MovePicker { chess_board, stage: Stage::TranspositionMove, tt_move, killers, killer_index: 0, countermove, history, captures, bad_captures: vec![], quiets, scored_quiets: vec![] }
+    }
+
+    // Removes "chess_move" from the quiet moves, returns None if it isn't one of them
+    fn take_quiet(&mut self, chess_move: Option<ChessMove>) -> Option<ChessMove> {
+        let chess_move = chess_move?;
+        let index = self.quiets.iter().position(|quiet| *quiet == chess_move)?;
+        Some(self.quiets.swap_remove(index))
+    }
+}
+
+impl Iterator for MovePicker<'_> {
+    type Item = ChessMove;
+
+    fn next(&mut self) -> Option<ChessMove> {
+        loop {
+            match self.stage {
+                Stage::TranspositionMove => {
+                    self.stage = Stage::GoodCaptures;
+                    if self.tt_move.is_some() {
+                        return self.tt_move;
+                    }
+                }
+                Stage::GoodCaptures => {
+                    if let Some(chess_move) = pick_best(&mut self.captures) {
+                        if self.chess_board.see_ge(chess_move, 0) {
+                            return Some(chess_move);
+                        }
+                        self.bad_captures.push(chess_move);
+                        continue;
+                    }
+                    self.stage = Stage::Killers;
+                }
+                Stage::Killers => {
+                    while self.killer_index < 2 {
+                        let killer = self.killers.moves[self.killer_index];
+                        self.killer_index += 1;
+                        if let Some(chess_move) = self.take_quiet(killer) {
+                            return Some(chess_move);
+                        }
+                    }
+                    self.stage = Stage::Countermove;
+                }
+                Stage::Countermove => {
+                    self.stage = Stage::Quiets;
+                    let countermove = self.take_quiet(self.countermove);
+                    let color = self.chess_board.inner.current_color;
+                    let history = self.history;
+                    self.scored_quiets = self.quiets.drain(..)
+                        .map(|chess_move| (chess_move, history.map_or(0, |history| history.get(color, chess_move))))
+                        .collect();
+                    if countermove.is_some() {
+                        return countermove;
+                    }
+                }
+                Stage::Quiets => {
+                    if let Some(chess_move) = pick_best(&mut self.scored_quiets) {
+                        return Some(chess_move);
+                    }
+                    self.stage = Stage::BadCaptures;
+                }
+                Stage::BadCaptures => {
+                    if self.bad_captures.is_empty() {
+                        self.stage = Stage::Done;
+                    } else {
+                        return Some(self.bad_captures.remove(0));
+                    }
+                }
+                Stage::Done => return None,
+            }
+        }
+    }
+}
+
+/// Returns true if the move captures a piece or promotes a pawn
+pub fn is_tactical(chess_board: &ChessBoard, chess_move: ChessMove) -> bool {
+    chess_move.promotion.is_some() || chess_board.captured_piece(chess_move).is_some()
+}
+
+// Most valuable victim first and least valuable attacker second, a promotion counts as capturing the piece it promotes to
+fn mvv_lva(chess_board: &ChessBoard, chess_move: ChessMove) -> i32 {
+    let promotion = chess_move.promotion.map_or(0, |piece_type| ORDER_VALUES[piece_type as usize]);
+    match chess_board.captured_piece(chess_move) {
+        Some(captured) => {
+            let attacker = chess_board.inner.get_piece_type(chess_move.src.as_bb());
+            (ORDER_VALUES[captured as usize] + promotion) * 16 - ORDER_VALUES[attacker as usize]
+        }
+        None => promotion * 16,
+    }
+}
+
+// Removes and returns the move with the highest score, the first of equal scores
+fn pick_best(moves: &mut Vec<(ChessMove, i32)>) -> Option<ChessMove> {
+    let (index, _) = moves.iter().enumerate().rev().max_by_key(|(_, (_, score))| *score)?;
+    Some(moves.remove(index).0)
+}
@@ -1,19 +1,22 @@
+use std::fmt;
+
 use crate::chess_board::*;
 use crate::promotion::*;
 use crate::types::*;
 
 use crate::core::piece::*;
-use crate::core::board::*;
 
 /// Represents the type of a chess move
 /// "Promotion" always implies a capture of pawn
 /// PieceType in Normal is the captured piece type, if None then there was no capture 
-#[derive(Debug)]
+/// "Drop" is a piece placed from the pocket, as in Crazyhouse
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum MoveType {
     Normal( Option<PieceType> ),
     Promotion,
     Castling,
     EnPassant,
+    Drop,
 }
 
 /// Contains the result from a chess move
@@ -23,8 +26,59 @@ pub enum MoveResult {
     PawnPromotionResolver (PawnPromotionResolver)
 }
 
+/// Represents a chess move which does not borrow the chess board it was generated from
+/// Promotion is the piece type the pawn is promoted to, None if the move is not a promotion
+/// Drop is the piece type placed from the pocket on dst, as in Crazyhouse, src is the same as dst for a drop
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct ChessMove {
+    pub src: Index,
+    pub dst: Index,
+    pub promotion: Option<PieceType>,
+    pub drop: Option<PieceType>,
+}
+
+impl ChessMove {
+    /// Returns a move of a piece on the board from "src" to "dst"
+    pub fn new(src: Index, dst: Index, promotion: Option<PieceType>) -> Self {
+        ChessMove { src, dst, promotion, drop: None }
+    }
+
+    /// Returns a drop of "piece_type" from the pocket on "dst"
+    pub fn new_drop(piece_type: PieceType, dst: Index) -> Self {
+        ChessMove { src: dst, dst, promotion: None, drop: Some(piece_type) }
+    }
+}
+
+/// Formats the move in UCI long algebraic notation, for example "e2e4" or "e7e8q"
+/// A drop is formatted as the piece letter, "@" and the square, for example "N@f3"
+impl fmt::Display for ChessMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(piece_type) = self.drop {
+            let letter = match piece_type {
+                PieceType::Pawn => 'P',
+                PieceType::Knight => 'N',
+                PieceType::Bishop => 'B',
+                PieceType::Rook => 'R',
+                PieceType::Queen => 'Q',
+                PieceType::King => 'K',
+            };
+            return write!(f, "{}@{}", letter, self.dst);
+        }
+        write!(f, "{}{}", self.src, self.dst)?;
+        match self.promotion {
+            Some(PieceType::Knight) => write!(f, "n"),
+            Some(PieceType::Bishop) => write!(f, "b"),
+            Some(PieceType::Rook) => write!(f, "r"),
+            Some(PieceType::Queen) => write!(f, "q"),
+            Some(PieceType::King) => write!(f, "k"),
+            _ => Ok(()),
+        }
+    }
+}
+
 /// Represents a chess move
 /// Contains the move source index the move destination index and a reference to the board the move was made on
+/// The destination of castling is the square the king ends on, or the square of the rook if the board is in Chess960 mode
 #[derive(Debug)]
 pub struct Move<'a> {
 
@@ -39,23 +93,26 @@ impl<'a> Move<'a> {
     /// MoveResult will just be the CLONED chess board with the move perfomed, except when there is a promotion
     ///     Then it will return a PawnPromotionResolver
     pub fn make_move(&self) -> (MoveResult, MoveType) {
-        let bb_dst = (1 as BitBoard) << self.dst.get();
+        let bb_dst = self.chess_board.to_internal_dst(self.src, self.dst).as_bb();
         let mut chess_board_clone = (*self.chess_board).clone();
 
         let move_type = self.get_move_type();
         chess_board_clone.inner.make_move(self.src.get(), bb_dst);
 
         if chess_board_clone.inner.need_to_resolve_promotion() {
-            (MoveResult::PawnPromotionResolver(PawnPromotionResolver { chess_board: chess_board_clone }), move_type)
+            let resolver = PawnPromotionResolver { chess_board: chess_board_clone, previous: Box::new(self.chess_board.clone()), src: self.src, dst: self.dst };
+            (MoveResult::PawnPromotionResolver(resolver), move_type)
         } else {
             chess_board_clone.inner.toggle_current_color();
+            let chess_move = ChessMove::new(self.src, self.dst, None);
+            self.chess_board.variant().after_move(self.chess_board, &mut chess_board_clone, chess_move);
             (MoveResult::ChessBoard(chess_board_clone), move_type)
         }
     }
     
     fn get_move_type(&self) -> MoveType {
         let bb_src = self.src.as_bb();
-        let bb_dst = self.dst.as_bb();
+        let bb_dst = self.chess_board.to_internal_dst(self.src, self.dst).as_bb();
 
         if self.chess_board.inner.is_castle(bb_src, bb_dst) {
             MoveType::Castling
@@ -0,0 +1,288 @@
+// https://www.chessprogramming.org/Perft
+use std::sync::atomic::{ AtomicU64, AtomicUsize, Ordering };
+use std::thread;
+
+use crate::chess_board::*;
+use crate::mv::*;
+use crate::promotion::*;
+
+use crate::core::board::*;
+use crate::core::chess_board as internal;
+use crate::core::move_generation::*;
+
+/// Options used when counting the leaf nodes of the move tree
+/// hash_size_mb is the size of the hash table in megabytes, no hash table is used if it is 0
+/// threads is the number of threads the root moves are split between
+#[derive(Debug, Clone, Copy)]
+pub struct PerftOptions {
+    pub hash_size_mb: usize,
+    pub threads: usize,
+}
+
+impl Default for PerftOptions {
+    fn default() -> Self {
+        PerftOptions { hash_size_mb: 0, threads: 1 }
+    }
+}
+
+/// Statistics about the leaf nodes of the legal move tree, the same columns as https://www.chessprogramming.org/Perft_Results
+/// Every count except nodes counts the moves leading to the leaf nodes
+/// Captures include en passant and promotions which capture a piece
+/// A discovered check is a check where the piece which moved is not one of the checking pieces
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub struct PerftStats {
+    pub nodes: u64,
+    pub captures: u64,
+    pub en_passants: u64,
+    pub castles: u64,
+    pub promotions: u64,
+    pub checks: u64,
+    pub discovered_checks: u64,
+    pub double_checks: u64,
+    pub checkmates: u64,
+}
+
+/// Returns the number of leaf nodes of the legal move tree with the given depth
+pub fn perft(chess_board: &ChessBoard, depth: usize) -> u64 {
+    perft_with_options(chess_board, depth, &PerftOptions::default())
+}
+
+/// Same as perft, but uses the given hash table size and number of threads
+pub fn perft_with_options(chess_board: &ChessBoard, depth: usize, options: &PerftOptions) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    perft_divide_with_options(chess_board, depth, options).iter().map(|(_, count)| count).sum()
+}
+
+/// Returns the number of leaf nodes below every legal root move, in the order of ChessBoard::legal_moves
+/// Returns an empty vector if depth is 0
+pub fn perft_divide(chess_board: &ChessBoard, depth: usize) -> Vec<(ChessMove, u64)> {
+    perft_divide_with_options(chess_board, depth, &PerftOptions::default())
+}
+
+/// Same as perft_divide, but uses the given hash table size and number of threads
+/// The hash table is only used for standard chess, since the position of a variant can have more state than the hash
+pub fn perft_divide_with_options(chess_board: &ChessBoard, depth: usize, options: &PerftOptions) -> Vec<(ChessMove, u64)> {
+    if depth == 0 {
+        return vec![];
+    }
+
+    let root_moves = chess_board.legal_moves();
+    let counts: Vec<AtomicU64> = root_moves.iter().map(|_| AtomicU64::new(0)).collect();
+    let next_move = AtomicUsize::new(0);
+    let table = PerftTable::new(options.hash_size_mb);
+
+    // Every thread takes the next root move which has not been counted yet, until there are none left
+    let worker = || {
+        loop {
+            let i = next_move.fetch_add(1, Ordering::Relaxed);
+            if i >= root_moves.len() {
+                break;
+            }
+            let count = if chess_board.is_standard() {
+                let internal_move = ChessMove { dst: chess_board.to_internal_dst(root_moves[i].src, root_moves[i].dst), ..root_moves[i] };
+                count_nodes(&make_move(&chess_board.inner, internal_move), depth - 1, table.as_ref())
+            } else {
+                count_variant_nodes(&chess_board.make_move(root_moves[i]).unwrap().0, depth - 1)
+            };
+            counts[i].store(count, Ordering::Relaxed);
+        }
+    };
+
+    let threads = options.threads.clamp(1, root_moves.len().max(1));
+    thread::scope(|scope| {
+        for _ in 1..threads {
+            scope.spawn(worker);
+        }
+        worker();
+    });
+
+    root_moves.into_iter().zip(counts.into_iter().map(AtomicU64::into_inner)).collect()
+}
+
+/// Returns statistics about the leaf nodes of the legal move tree with the given depth
+/// This is a lot slower than perft, since every leaf node has to be visited
+/// The moves are generated by the rules of standard chess, whatever the variant of the board is
+pub fn perft_stats(chess_board: &ChessBoard, depth: usize) -> PerftStats {
+    let mut stats = PerftStats::default();
+    if depth == 0 {
+        stats.nodes = 1;
+    } else {
+        collect_stats(&chess_board.inner, depth, &mut stats);
+    }
+    stats
+}
+
+fn make_move(chess_board: &internal::ChessBoard, chess_move: ChessMove) -> internal::ChessBoard {
+    let mut chess_board = chess_board.clone();
+    chess_board.make_move(chess_move.src.get(), chess_move.dst.as_bb());
+    if let Some(promotion) = chess_move.promotion {
+        chess_board.resolve_promotion(promotion);
+    }
+    chess_board.toggle_current_color();
+    chess_board
+}
+
+fn count_nodes(chess_board: &internal::ChessBoard, depth: usize, table: Option<&PerftTable>) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+
+    // Leaves are not worth storing since bulk counting them is cheaper than hashing
+    let hash = match table {
+        Some(table) if depth > 1 => {
+            let hash = chess_board.hash();
+            if let Some(count) = table.probe(hash, depth) {
+                return count;
+            }
+            Some(hash)
+        }
+        _ => None,
+    };
+
+    let mut count: u64 = 0;
+    let masks = LegalMasks::new(chess_board);
+    let mut pieces = chess_board.all_pieces[chess_board.current_color as usize];
+    while pieces != 0 {
+        let src = pop_lsb(&mut pieces);
+        let piece_type = chess_board.get_piece_type((1 as BitBoard) << src);
+        let mut bb_moves = get_move_generator(piece_type)(chess_board, &masks, src);
+        let bb_promotions = if piece_type == PieceType::Pawn { bb_moves & PROMOTION_RANKS } else { 0 };
+
+        // Bulk counting: the number of moves is the number of leaf nodes
+        if depth == 1 {
+            count += (bb_moves.count_ones() + bb_promotions.count_ones() * (PROMOTION_PIECE_TYPES.len() as u32 - 1)) as u64;
+            continue;
+        }
+
+        while bb_moves != 0 {
+            let dst = pop_lsb(&mut bb_moves);
+            let mut child = chess_board.clone();
+            child.make_move(src, (1 as BitBoard) << dst);
+            if child.need_to_resolve_promotion() {
+                for promotion in PROMOTION_PIECE_TYPES {
+                    let mut child = child.clone();
+                    child.resolve_promotion(promotion);
+                    child.toggle_current_color();
+                    count += count_nodes(&child, depth - 1, table);
+                }
+            } else {
+                child.toggle_current_color();
+                count += count_nodes(&child, depth - 1, table);
+            }
+        }
+    }
+
+    if let (Some(table), Some(hash)) = (table, hash) {
+        table.store(hash, depth, count);
+    }
+    count
+}
+
+// Variants are played through the public ChessBoard, which is slower but follows every rule of the variant
+fn count_variant_nodes(chess_board: &ChessBoard, depth: usize) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let moves = chess_board.legal_moves();
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+    moves.into_iter().map(|chess_move| count_variant_nodes(&chess_board.make_move(chess_move).unwrap().0, depth - 1)).sum()
+}
+
+fn collect_stats(chess_board: &internal::ChessBoard, depth: usize, stats: &mut PerftStats) {
+    let color = chess_board.current_color as usize;
+    let masks = LegalMasks::new(chess_board);
+    let mut pieces = chess_board.all_pieces[color];
+    while pieces != 0 {
+        let src = pop_lsb(&mut pieces);
+        let bb_src = (1 as BitBoard) << src;
+        let piece_type = chess_board.get_piece_type(bb_src);
+        let mut bb_moves = get_move_generator(piece_type)(chess_board, &masks, src);
+        while bb_moves != 0 {
+            let bb_dst = (1 as BitBoard) << pop_lsb(&mut bb_moves);
+            let is_castle = chess_board.is_castle(bb_src, bb_dst);
+            let is_en_passant = chess_board.is_en_passant(bb_src, bb_dst);
+            let is_capture = chess_board.is_capture(bb_src, bb_dst) || is_en_passant;
+
+            let mut child = chess_board.clone();
+            child.make_move(src, bb_dst);
+            let is_promotion = child.need_to_resolve_promotion();
+            let promotions: &[Option<PieceType>] = if is_promotion { &PROMOTIONS } else { &[None] };
+
+            for promotion in promotions {
+                let mut child = child.clone();
+                if let Some(promotion) = *promotion {
+                    child.resolve_promotion(promotion);
+                }
+                child.toggle_current_color();
+
+                if depth > 1 {
+                    collect_stats(&child, depth - 1, stats);
+                    continue;
+                }
+
+                // The squares of the pieces which moved, for castling this includes the rook
+                let own_rooks = |chess_board: &internal::ChessBoard| chess_board.pieces[PieceType::Rook as usize] & chess_board.all_pieces[color];
+                let moved_pieces = bb_dst | if is_castle { own_rooks(&child) & !own_rooks(chess_board) } else { 0 };
+                let checkers = get_pieces_attacking_king(&child, chess_board.current_color);
+
+                stats.nodes += 1;
+                stats.captures += is_capture as u64;
+                stats.en_passants += is_en_passant as u64;
+                stats.castles += is_castle as u64;
+                stats.promotions += is_promotion as u64;
+                stats.checks += (checkers != 0) as u64;
+                stats.discovered_checks += (checkers != 0 && checkers & moved_pieces == 0) as u64;
+                stats.double_checks += (checkers.count_ones() > 1) as u64;
+                stats.checkmates += (checkers != 0 && !has_legal_moves(&child)) as u64;
+            }
+        }
+    }
+}
+
+const PROMOTIONS: [Option<PieceType>; 4] = [ Some(PieceType::Knight), Some(PieceType::Bishop), Some(PieceType::Rook), Some(PieceType::Queen) ];
+
+// Hash table of node counts which can be shared between threads without locking
+// The key is stored xored with the data, so an entry torn by two threads writing at once is never accepted
+// https://www.chessprogramming.org/Shared_Hash_Table#Lockless
+struct PerftTable {
+    entries: Vec<PerftEntry>,
+}
+
+struct PerftEntry {
+    key: AtomicU64,
+    data: AtomicU64,    // The lowest 8 bits contain the depth, the rest the node count
+}
+
+impl PerftTable {
+    fn new(size_mb: usize) -> Option<PerftTable> {
+        let entry_count = size_mb * 1024 * 1024 / std::mem::size_of::<PerftEntry>();
+        if entry_count == 0 {
+            return None;
+        }
+        let entries = (0..entry_count).map(|_| PerftEntry { key: AtomicU64::new(0), data: AtomicU64::new(0) }).collect();
+        Some(PerftTable { entries })
+    }
+
+    fn probe(&self, hash: u64, depth: usize) -> Option<u64> {
+        let entry = &self.entries[(hash % self.entries.len() as u64) as usize];
+        let key = entry.key.load(Ordering::Relaxed);
+        let data = entry.data.load(Ordering::Relaxed);
+        if key ^ data == hash && (data & 0xFF) as usize == depth {
+            Some(data >> 8)
+        } else {
+            None
+        }
+    }
+
+    fn store(&self, hash: u64, depth: usize, count: u64) {
+        assert!(depth <= 0xFF);
+        let entry = &self.entries[(hash % self.entries.len() as u64) as usize];
+        let data = (count << 8) | depth as u64;
+        entry.key.store(hash ^ data, Ordering::Relaxed);
+        entry.data.store(data, Ordering::Relaxed);
+    }
+}
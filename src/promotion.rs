@@ -1,10 +1,18 @@
 use crate::chess_board::*;
 use crate::mv::*;
+use crate::types::*;
+
+/// The piece types a pawn can be promoted to
+pub const PROMOTION_PIECE_TYPES: [PieceType; 4] = [ PieceType::Knight, PieceType::Bishop, PieceType::Rook, PieceType::Queen ];
 
 /// Structure used for resolving a chess promotion
+/// Previous, src and dst are the position and the move the pawn was promoted by
 #[derive(Debug)]
 pub struct PawnPromotionResolver {
     pub chess_board: ChessBoard,
+    pub(crate) previous: Box<ChessBoard>,
+    pub(crate) src: Index,
+    pub(crate) dst: Index,
 }
 
 // TODO: Merge the different resolves into one, by takinga bounded piece type as argument.
@@ -35,10 +43,18 @@ impl PawnPromotionResolver {
         self.resolve(PieceType::Queen)
     }
 
-    fn resolve(&self, piece_type: PieceType) -> (ChessBoard, MoveType) {
+    /// Resolves the promotion by replacing the promoted pawn with a king, which is only legal in variants such as Antichess
+    /// Returns None if the variant doesn't allow it, otherwise a CLONE of the chess_board and MoveType::Promotion
+    pub fn resolve_king(&self) -> Option<(ChessBoard, MoveType)> {
+        self.previous.variant().promotion_piece_types().contains(&PieceType::King).then(|| self.resolve(PieceType::King))
+    }
+
+    pub(crate) fn resolve(&self, piece_type: PieceType) -> (ChessBoard, MoveType) {
         let mut chess_board_clone = self.chess_board.clone();
         chess_board_clone.inner.resolve_promotion(piece_type);
         chess_board_clone.inner.toggle_current_color();
+        let chess_move = ChessMove::new(self.src, self.dst, Some(piece_type));
+        self.previous.variant().after_move(&self.previous, &mut chess_board_clone, chess_move);
         (chess_board_clone, MoveType::Promotion)
     }
 }
\ No newline at end of file
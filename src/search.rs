@@ -0,0 +1,775 @@
+// https://www.chessprogramming.org/Alpha-Beta
+// https://www.chessprogramming.org/Iterative_Deepening
+// https://www.chessprogramming.org/Lazy_SMP
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{ AtomicBool, AtomicU64, Ordering };
+use std::thread;
+use std::time::{ Duration, Instant };
+
+use crate::chess_board::*;
+use crate::eval::*;
+use crate::move_picker::*;
+use crate::mv::*;
+use crate::transposition::*;
+
+use crate::core::piece::*;
+
+/// The score of checkmating on the current move, a mate in "ply" half moves scores MATE_SCORE - ply
+pub const MATE_SCORE: i32 = 32000;
+/// The deepest ply the search reaches
+pub const MAX_PLY: usize = 128;
+/// The size of the transposition table of a new searcher in megabytes
+pub const DEFAULT_HASH_SIZE_MB: usize = 16;
+
+const INFINITY: i32 = MATE_SCORE + 1;
+// Scores closer to MATE_SCORE than this are mates
+const MATE_BOUND: i32 = MATE_SCORE - MAX_PLY as i32;
+// The stop flag and the time are checked once every this many nodes
+const CHECK_INTERVAL: u64 = 1024;
+
+/// The limits of a search, the search stops as soon as any of them is reached
+/// A search without limits only stops when the stop flag is set or at MAX_PLY
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SearchLimits {
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    pub time: Option<Duration>,
+}
+
+/// The moves left until the next time control when the time control doesn't say
+pub const DEFAULT_MOVES_TO_GO: u32 = 30;
+/// The time kept back for the communication with the interface
+pub const MOVE_OVERHEAD: Duration = Duration::from_millis(30);
+
+/// The time to spend on a move with "time" left on the clock, for the protocol front ends
+/// The remaining time is divided by the moves until the next time control, DEFAULT_MOVES_TO_GO if None, and most of the increment is added
+/// It never uses more than the remaining time minus MOVE_OVERHEAD, and is at least a millisecond
+pub fn time_budget(time: Duration, increment: Duration, moves_to_go: Option<u32>) -> Duration {
+    let moves_to_go = moves_to_go.unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+    let budget = time / moves_to_go + increment * 3 / 4;
+    budget.min(time.saturating_sub(MOVE_OVERHEAD)).max(Duration::from_millis(1))
+}
+
+/// The score of a position from the view of the side to move
+/// Mate is the number of moves until checkmate, negative if the side to move gets checkmated
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Score {
+    Centipawns(i32),
+    Mate(i32),
+}
+
+impl Score {
+    /// Returns the score of a value of the search, values within MAX_PLY of MATE_SCORE are mates
+    pub fn from_value(value: i32) -> Self {
+        if value > MATE_BOUND {
+            Score::Mate((MATE_SCORE - value + 1) / 2)
+        } else if value < -MATE_BOUND {
+            Score::Mate(-(MATE_SCORE + value) / 2)
+        } else {
+            Score::Centipawns(value)
+        }
+    }
+}
+
+/// Formats the score as in the UCI protocol, for example "cp 35" or "mate -2"
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Score::Centipawns(centipawns) => write!(f, "cp {}", centipawns),
+            Score::Mate(moves) => write!(f, "mate {}", moves),
+        }
+    }
+}
+
+/// The parameters of the pruning, reductions and extensions of the search, every one of them can be turned off to compare it in self-play
+/// Depths and margins are in plies and centipawns
+#[derive(Debug, Clone, Copy)]
+pub struct SearchParams {
+    /// Skipping a move and searching with a reduced depth, a position is cut off if the opponent can't reach beta even then
+    /// Not used when the side to move has only its king and pawns, where having to move may be a disadvantage
+    pub null_move: bool,
+    pub null_move_min_depth: i32,
+    /// The depth is reduced by null_move_reduction plus the depth divided by null_move_depth_divisor
+    pub null_move_reduction: i32,
+    pub null_move_depth_divisor: i32,
+    /// Searching quiet moves late in the move order with a reduced depth, and again with the full depth if they raise alpha
+    /// The reduction is lmr_base + ln(depth) * ln(move number) / lmr_divisor
+    pub late_move_reductions: bool,
+    pub lmr_min_depth: i32,
+    pub lmr_min_moves: usize,
+    pub lmr_base: f64,
+    pub lmr_divisor: f64,
+    /// Skipping quiet moves near the leaves if the static evaluation plus futility_margin per ply can't reach alpha
+    pub futility_pruning: bool,
+    pub futility_max_depth: i32,
+    pub futility_margin: i32,
+    /// Cutting off positions near the leaves if the static evaluation minus reverse_futility_margin per ply is still above beta
+    pub reverse_futility_pruning: bool,
+    pub reverse_futility_max_depth: i32,
+    pub reverse_futility_margin: i32,
+    /// Searching positions in check one ply deeper
+    pub check_extensions: bool,
+    /// Searching the root with a window of aspiration_window around the score of the previous iteration, widened when the score falls outside
+    pub aspiration_windows: bool,
+    pub aspiration_min_depth: u32,
+    pub aspiration_window: i32,
+}
+
+impl Default for SearchParams {
+    fn default() -> Self {
+        SearchParams {
+            null_move: true,
+            null_move_min_depth: 3,
+            null_move_reduction: 3,
+            null_move_depth_divisor: 6,
+            late_move_reductions: true,
+            lmr_min_depth: 3,
+            lmr_min_moves: 3,
+            lmr_base: 0.75,
+            lmr_divisor: 2.25,
+            futility_pruning: true,
+            futility_max_depth: 3,
+            futility_margin: 120,
+            reverse_futility_pruning: true,
+            reverse_futility_max_depth: 6,
+            reverse_futility_margin: 90,
+            check_extensions: true,
+            aspiration_windows: true,
+            aspiration_min_depth: 4,
+            aspiration_window: 25,
+        }
+    }
+}
+
+impl SearchParams {
+    /// Returns parameters with every pruning, reduction and extension turned off, a plain alpha-beta search
+    pub fn none() -> Self {
+        SearchParams {
+            null_move: false,
+            late_move_reductions: false,
+            futility_pruning: false,
+            reverse_futility_pruning: false,
+            check_extensions: false,
+            aspiration_windows: false,
+            ..SearchParams::default()
+        }
+    }
+}
+
+/// Information about a line of a completed iteration of the search
+/// "multi_pv" is the rank of the line, 1 for the best line
+#[derive(Debug, Clone)]
+pub struct SearchInfo {
+    pub depth: u32,
+    pub multi_pv: usize,
+    pub score: Score,
+    pub pv: Vec<ChessMove>,
+    pub nodes: u64,
+    pub nps: u64,
+    pub time: Duration,
+}
+
+/// The result of a search, from the deepest completed iteration
+/// The best move is None if the side to move has no legal moves
+/// "lines" are the best lines with different first moves, best first, as many as the MultiPV setting and the legal moves allow
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    pub best_move: Option<ChessMove>,
+    pub score: Score,
+    pub depth: u32,
+    pub pv: Vec<ChessMove>,
+    pub lines: Vec<SearchLine>,
+    pub nodes: u64,
+}
+
+/// A line of the search, the score after its first move and its principal variation
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SearchLine {
+    pub score: Score,
+    pub pv: Vec<ChessMove>,
+}
+
+/// Searches for the best move with negamax alpha-beta, iterative deepening and a quiescence search of captures
+/// Positions are played by the rules of their variant, standard chess uses the fast move generation
+/// The transposition table is kept between searches, until it is cleared or resized
+pub struct Searcher {
+    stop: Arc<AtomicBool>,
+    transposition_table: TranspositionTable,
+    params: SearchParams,
+    threads: usize,
+    multi_pv: usize,
+}
+
+impl Searcher {
+    /// Returns a searcher which stops as soon as "stop" is set, the flag is never cleared by the searcher
+    pub fn new(stop: Arc<AtomicBool>) -> Self {
+        Searcher { stop, transposition_table: TranspositionTable::new(DEFAULT_HASH_SIZE_MB), params: SearchParams::default(), threads: 1, multi_pv: 1 }
+    }
+
+    /// Returns the flag which stops the search
+    pub fn stop_flag(&self) -> Arc<AtomicBool> {
+        self.stop.clone()
+    }
+
+    /// Returns the transposition table
+    pub fn transposition_table(&self) -> &TranspositionTable {
+        &self.transposition_table
+    }
+
+    /// Returns the transposition table, to resize it
+    pub fn transposition_table_mut(&mut self) -> &mut TranspositionTable {
+        &mut self.transposition_table
+    }
+
+    /// Returns the parameters of the pruning, reductions and extensions
+    pub fn params(&self) -> &SearchParams {
+        &self.params
+    }
+
+    /// Replaces the parameters of the pruning, reductions and extensions
+    pub fn set_params(&mut self, params: SearchParams) {
+        self.params = params;
+    }
+
+    /// Returns the number of threads which search together
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    /// Sets the number of threads which search together, at least 1
+    /// The helper threads share the transposition table with the main thread and search the same position with their own move ordering tables,
+    ///     every other one of them a ply deeper, so that they fill the table with results the main thread uses
+    /// A search with a single thread is deterministic
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads.max(1);
+    }
+
+    /// Returns the number of lines the search finds
+    pub fn multi_pv(&self) -> usize {
+        self.multi_pv
+    }
+
+    /// Sets the number of lines the search finds, at least 1
+    /// Every iteration searches the root once per line, without the first moves of the lines found before, and reports every line
+    pub fn set_multi_pv(&mut self, multi_pv: usize) {
+        self.multi_pv = multi_pv.max(1);
+    }
+
+    /// Searches the position until one of the limits is reached or the stop flag is set
+    /// "history" are the hashes of the positions before it, oldest first, as returned by Game::history, to recognize repetitions
+    /// "on_info" is called for every line of every iteration completed by the main thread, with the nodes of every thread
+    /// The result is that of the thread which completed the deepest iteration, the main thread for equal depths
+    pub fn search(&mut self, chess_board: &ChessBoard, history: &[u64], limits: SearchLimits, mut on_info: impl FnMut(&SearchInfo)) -> SearchResult {
+        self.transposition_table.new_search();
+        let root_moves = legal_moves(chess_board);
+        let mut result = SearchResult {
+            best_move: root_moves.first().copied(),
+            score: Score::Centipawns(0),
+            depth: 0,
+            pv: vec![],
+            lines: vec![],
+            nodes: 0,
+        };
+        if root_moves.is_empty() {
+            return result;
+        }
+
+        let shared = Shared { finished: AtomicBool::new(false), nodes: AtomicU64::new(0) };
+        let start = Instant::now();
+        let max_depth = limits.depth.unwrap_or(MAX_PLY as u32).clamp(1, MAX_PLY as u32);
+        let (main, helpers) = thread::scope(|scope| {
+            let helpers: Vec<_> = (1..self.threads).map(|id| {
+                let mut worker = Worker::new(self, &shared, limits, start, history);
+                scope.spawn(move || {
+                    let iteration = worker.iterate(chess_board, max_depth, id as u32 % 2, &mut |_| {});
+                    (iteration, worker.nodes)
+                })
+            }).collect();
+
+            let mut worker = Worker::new(self, &shared, limits, start, history);
+            let iteration = worker.iterate(chess_board, max_depth, 0, &mut on_info);
+            shared.finished.store(true, Ordering::Relaxed);
+            let helpers: Vec<_> = helpers.into_iter().map(|helper| helper.join().unwrap()).collect();
+            ((iteration, worker.nodes), helpers)
+        });
+
+        result.nodes = main.1 + helpers.iter().map(|(_, nodes)| nodes).sum::<u64>();
+        if let Some(best) = deepest_iteration(main.0, helpers.into_iter().map(|(iteration, _)| iteration)) {
+            result.lines = best.lines.into_iter().map(|line| SearchLine { score: Score::from_value(line.value), pv: line.pv }).collect();
+            result.best_move = result.lines[0].pv.first().copied().or(result.best_move);
+            result.score = result.lines[0].score;
+            result.depth = best.depth;
+            result.pv = result.lines[0].pv.clone();
+        }
+        result
+    }
+}
+
+// The state shared by the threads of a search
+struct Shared {
+    // Set when the main thread has finished, which stops the helper threads
+    finished: AtomicBool,
+    // The nodes searched by every thread, counted in steps of CHECK_INTERVAL
+    nodes: AtomicU64,
+}
+
+// A completed iteration of the search, with its lines best first
+struct Iteration {
+    depth: u32,
+    lines: Vec<Line>,
+}
+
+// The deepest of the iterations completed by the threads, the main thread wins ties since it reported its iterations
+fn deepest_iteration(main: Option<Iteration>, helpers: impl Iterator<Item = Option<Iteration>>) -> Option<Iteration> {
+    helpers.flatten().fold(main, |best, iteration| match best {
+        Some(best) if best.depth >= iteration.depth => Some(best),
+        _ => Some(iteration),
+    })
+}
+
+struct Line {
+    value: i32,
+    pv: Vec<ChessMove>,
+}
+
+// The state of the search of a single thread
+struct Worker<'a> {
+    stop: &'a AtomicBool,
+    shared: &'a Shared,
+    transposition_table: &'a TranspositionTable,
+    params: SearchParams,
+    multi_pv: usize,
+    limits: SearchLimits,
+    start: Instant,
+    nodes: u64,
+    stopped: bool,
+    // The hashes of the positions of the game and of the search up to the current node
+    hashes: Vec<u64>,
+    // The principal variation from every ply
+    pv: Vec<Vec<ChessMove>>,
+    // The best move of the previous iteration, searched first
+    root_move: Option<ChessMove>,
+    // The first moves of the lines found before in the iteration, not searched at the root
+    excluded_moves: Vec<ChessMove>,
+    killers: Vec<Killers>,
+    history: HistoryTable,
+    countermoves: CountermoveTable,
+    // The move which led to the node of every ply, None after a null move
+    previous_moves: Vec<Option<ChessMove>>,
+}
+
+impl<'a> Worker<'a> {
+    fn new(searcher: &'a Searcher, shared: &'a Shared, limits: SearchLimits, start: Instant, history: &[u64]) -> Self {
+        Worker {
+            stop: &searcher.stop,
+            shared,
+            transposition_table: &searcher.transposition_table,
+            params: searcher.params,
+            multi_pv: searcher.multi_pv,
+            limits,
+            start,
+            nodes: 0,
+            stopped: false,
+            hashes: history.to_vec(),
+            pv: vec![vec![]; MAX_PLY + 1],
+            root_move: None,
+            excluded_moves: vec![],
+            killers: vec![Killers::new(); MAX_PLY + 1],
+            history: HistoryTable::new(),
+            countermoves: CountermoveTable::new(),
+            previous_moves: vec![None; MAX_PLY + 1],
+        }
+    }
+
+    // Searches with iterative deepening up to "max_depth", "depth_offset" plies deeper than the iteration number
+    // Returns the deepest completed iteration, an iteration which is stopped is discarded
+    fn iterate(&mut self, chess_board: &ChessBoard, max_depth: u32, depth_offset: u32, on_info: &mut dyn FnMut(&SearchInfo)) -> Option<Iteration> {
+        let evaluation = Evaluation::new(chess_board);
+        let line_count = self.multi_pv.min(legal_moves(chess_board).len());
+        let mut completed: Option<Iteration> = None;
+        for depth in (1 + depth_offset).min(max_depth)..=max_depth {
+            if self.stop.load(Ordering::Relaxed) || self.shared.finished.load(Ordering::Relaxed) {
+                break;
+            }
+            // https://www.chessprogramming.org/Principal_Variation#MultiPV
+            self.excluded_moves.clear();
+            let mut lines = vec![];
+            for index in 0..line_count {
+                let previous = completed.as_ref().and_then(|iteration| iteration.lines.get(index));
+                self.root_move = previous.and_then(|line| line.pv.first().copied());
+                let previous = previous.map_or(Score::Centipawns(0), |line| Score::from_value(line.value));
+                let value = self.aspiration(chess_board, evaluation, depth, previous);
+                if self.stopped {
+                    break;
+                }
+                let pv = self.pv[0].clone();
+                self.excluded_moves.extend(pv.first());
+                lines.push(Line { value, pv });
+            }
+            if self.stopped {
+                break;
+            }
+
+            lines.sort_by_key(|line| -line.value);
+            let time = self.start.elapsed();
+            let nodes = self.total_nodes();
+            for (index, line) in lines.iter().enumerate() {
+                on_info(&SearchInfo {
+                    depth,
+                    multi_pv: index + 1,
+                    score: Score::from_value(line.value),
+                    pv: line.pv.clone(),
+                    nodes,
+                    nps: nodes * 1000 / (time.as_millis() as u64).max(1),
+                    time,
+                });
+            }
+            completed = Some(Iteration { depth, lines });
+        }
+        self.shared.nodes.fetch_add(self.nodes % CHECK_INTERVAL, Ordering::Relaxed);
+        completed
+    }
+
+    // The nodes searched by every thread, exact with a single thread
+    fn total_nodes(&self) -> u64 {
+        self.shared.nodes.load(Ordering::Relaxed) + self.nodes % CHECK_INTERVAL
+    }
+
+    // Counts the node and stops the search if a limit is reached
+    fn visit(&mut self) {
+        self.nodes += 1;
+        if self.nodes.is_multiple_of(CHECK_INTERVAL) {
+            self.shared.nodes.fetch_add(CHECK_INTERVAL, Ordering::Relaxed);
+            if self.stop.load(Ordering::Relaxed) || self.shared.finished.load(Ordering::Relaxed)
+                    || self.limits.time.is_some_and(|time| self.start.elapsed() >= time) {
+                self.stopped = true;
+            }
+        }
+        if self.limits.nodes.is_some_and(|nodes| self.total_nodes() >= nodes) {
+            self.stopped = true;
+        }
+    }
+
+    // A position is drawn by the fifty-move rule, by a repetition since the last pawn move or capture or by insufficient material
+    fn is_draw(&self, chess_board: &ChessBoard, hash: u64) -> bool {
+        let half_moves = chess_board.inner.half_moves as usize;
+        if half_moves >= 100 {
+            return true;
+        }
+        let repeated = self.hashes.iter().rev().take(half_moves).skip(1).step_by(2).any(|previous| *previous == hash);
+        repeated || (!chess_board.has_mating_material(PieceColor::White) && !chess_board.has_mating_material(PieceColor::Black))
+    }
+
+    // Searches the root in a window around the score of the previous iteration, which is widened until the score is inside it
+    // https://www.chessprogramming.org/Aspiration_Windows
+    fn aspiration(&mut self, chess_board: &ChessBoard, evaluation: Evaluation, depth: u32, previous: Score) -> i32 {
+        let (Score::Centipawns(previous), true) = (previous, self.params.aspiration_windows && depth >= self.params.aspiration_min_depth) else {
+            return self.negamax(chess_board, evaluation, depth as i32, 0, -INFINITY, INFINITY);
+        };
+        let mut window = self.params.aspiration_window;
+        let (mut alpha, mut beta) = (previous - window, previous + window);
+        loop {
+            let value = self.negamax(chess_board, evaluation, depth as i32, 0, alpha, beta);
+            if self.stopped {
+                return value;
+            }
+            window *= 2;
+            if value <= alpha {
+                alpha = (value - window).max(-INFINITY);
+            } else if value >= beta {
+                beta = (value + window).min(INFINITY);
+            } else {
+                return value;
+            }
+        }
+    }
+
+    fn negamax(&mut self, chess_board: &ChessBoard, evaluation: Evaluation, mut depth: i32, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        self.pv[ply].clear();
+        let in_check = chess_board.variant().is_check(chess_board);
+        if in_check && self.params.check_extensions && ply < MAX_PLY {
+            depth += 1;
+        }
+        if depth <= 0 {
+            return self.quiescence(chess_board, evaluation, ply, alpha, beta);
+        }
+        self.visit();
+        if self.stopped {
+            return 0;
+        }
+
+        let hash = chess_board.hash();
+        if ply > 0 && self.is_draw(chess_board, hash) {
+            return 0;
+        }
+        if let Some(value) = outcome_value(chess_board, ply) {
+            return value;
+        }
+        if ply >= MAX_PLY {
+            return static_value(chess_board, evaluation);
+        }
+
+        // The entry may cut off the search, except at the root which has to find a move
+        let entry = self.transposition_table.probe(hash, ply);
+        if let Some(entry) = entry && ply > 0 && entry.depth as i32 >= depth {
+            let cutoff = match entry.bound {
+                Bound::Exact => true,
+                Bound::Lower => entry.score >= beta,
+                Bound::Upper => entry.score <= alpha,
+            };
+            if cutoff {
+                return entry.score;
+            }
+        }
+
+        // https://www.chessprogramming.org/Reverse_Futility_Pruning
+        let static_eval = static_value(chess_board, evaluation);
+        let prunable = ply > 0 && !in_check && beta.abs() < MATE_BOUND;
+        if prunable && self.params.reverse_futility_pruning && depth <= self.params.reverse_futility_max_depth
+                && static_eval - self.params.reverse_futility_margin * depth >= beta {
+            return static_eval;
+        }
+
+        // https://www.chessprogramming.org/Null_Move_Pruning
+        let inner = &chess_board.inner;
+        let non_pawn_pieces = inner.all_pieces[inner.current_color as usize] & !(inner.pieces[PieceType::Pawn as usize] | inner.pieces[PieceType::King as usize]);
+        if prunable && self.params.null_move && chess_board.is_standard() && depth >= self.params.null_move_min_depth
+                && self.previous_moves[ply].is_some() && non_pawn_pieces != 0 && static_eval >= beta {
+            let reduction = self.params.null_move_reduction + depth / self.params.null_move_depth_divisor.max(1);
+            self.hashes.push(hash);
+            self.previous_moves[ply + 1] = None;
+            let value = -self.negamax(&make_null_move(chess_board), evaluation, depth - 1 - reduction, ply + 1, -beta, -beta + 1);
+            self.hashes.pop();
+            if self.stopped {
+                return 0;
+            }
+            if value >= beta {
+                // A mate found after skipping a move isn't proven
+                return if value >= MATE_BOUND { beta } else { value };
+            }
+        }
+        let futile = prunable && self.params.futility_pruning && depth <= self.params.futility_max_depth
+            && static_eval + self.params.futility_margin * depth <= alpha;
+
+        let tt_move = if ply == 0 { self.root_move } else { None }.or(entry.and_then(|entry| entry.best_move));
+        let countermove = self.previous_moves[ply].and_then(|previous| self.countermoves.get(previous));
+        // The moves are collected since the heuristics change while they are searched
+        let mut moves: Vec<ChessMove> = MovePicker::new(chess_board, tt_move, self.killers[ply], countermove, &self.history).collect();
+        if moves.is_empty() {
+            return if in_check { -MATE_SCORE + ply as i32 } else { 0 };
+        }
+
+        if ply == 0 {
+            moves.retain(|chess_move| !self.excluded_moves.contains(chess_move));
+        }
+
+        self.hashes.push(hash);
+        let original_alpha = alpha;
+        let mut best = -INFINITY;
+        let mut best_move = None;
+        let mut quiets = vec![];
+        for (index, chess_move) in moves.into_iter().enumerate() {
+            let quiet = !is_tactical(chess_board, chess_move);
+            let child = make_move(chess_board, chess_move);
+            let gives_check = quiet && child.variant().is_check(&child);
+            // https://www.chessprogramming.org/Futility_Pruning
+            if futile && quiet && !gives_check && index > 0 && best > -MATE_BOUND {
+                continue;
+            }
+
+            let mut child_evaluation = evaluation;
+            child_evaluation.update(chess_board, &child);
+            self.previous_moves[ply + 1] = Some(chess_move);
+
+            // https://www.chessprogramming.org/Late_Move_Reductions
+            let mut reduction = 0;
+            if self.params.late_move_reductions && quiet && !in_check && !gives_check
+                    && depth >= self.params.lmr_min_depth && index >= self.params.lmr_min_moves {
+                let log_reduction = self.params.lmr_base + (depth as f64).ln() * ((index + 1) as f64).ln() / self.params.lmr_divisor;
+                reduction = (log_reduction as i32).clamp(0, (depth - 2).max(0));
+            }
+            let mut value = -INFINITY;
+            if reduction > 0 {
+                value = -self.negamax(&child, child_evaluation, depth - 1 - reduction, ply + 1, -alpha - 1, -alpha);
+            }
+            if reduction == 0 || (value > alpha && !self.stopped) {
+                value = -self.negamax(&child, child_evaluation, depth - 1, ply + 1, -beta, -alpha);
+            }
+            if self.stopped {
+                break;
+            }
+            if value > best {
+                best = value;
+                if value > alpha {
+                    alpha = value;
+                    best_move = Some(chess_move);
+                    self.update_pv(ply, chess_move);
+                }
+                if value >= beta {
+                    if quiet {
+                        self.update_quiet_heuristics(chess_board, ply, depth, chess_move, &quiets);
+                    }
+                    break;
+                }
+            }
+            if quiet {
+                quiets.push(chess_move);
+            }
+        }
+        self.hashes.pop();
+
+        // The root is only stored when every move has been searched
+        if !self.stopped && (ply > 0 || self.excluded_moves.is_empty()) {
+            let bound = if best >= beta {
+                Bound::Lower
+            } else if best > original_alpha {
+                Bound::Exact
+            } else {
+                Bound::Upper
+            };
+            self.transposition_table.store(hash, ply, depth as u8, bound, best, best_move);
+        }
+        best
+    }
+
+    // Searches captures and promotions until the position is quiet, the side to move may stand pat on the static evaluation
+    // In check every evasion is searched instead, so checkmate is recognized
+    // https://www.chessprogramming.org/Quiescence_Search
+    fn quiescence(&mut self, chess_board: &ChessBoard, evaluation: Evaluation, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        self.pv[ply].clear();
+        self.visit();
+        if self.stopped {
+            return 0;
+        }
+        if let Some(value) = outcome_value(chess_board, ply) {
+            return value;
+        }
+
+        let stand_pat = static_value(chess_board, evaluation);
+        if ply >= MAX_PLY {
+            return stand_pat;
+        }
+        let in_check = chess_board.variant().is_check(chess_board);
+        let mut best = -INFINITY;
+        if !in_check {
+            if stand_pat >= beta {
+                return stand_pat;
+            }
+            alpha = alpha.max(stand_pat);
+            best = stand_pat;
+        }
+
+        let moves: Vec<ChessMove> = if in_check {
+            MovePicker::new(chess_board, None, Killers::new(), None, &self.history).collect()
+        } else {
+            MovePicker::captures(chess_board, None).collect()
+        };
+        if in_check && moves.is_empty() {
+            return -MATE_SCORE + ply as i32;
+        }
+        for chess_move in moves {
+            // Captures which lose material are skipped, the exchange is only known by the rules of standard chess
+            if !in_check && chess_board.is_standard() && !chess_board.see_ge(chess_move, 0) {
+                continue;
+            }
+            let child = make_move(chess_board, chess_move);
+            let mut child_evaluation = evaluation;
+            child_evaluation.update(chess_board, &child);
+            let value = -self.quiescence(&child, child_evaluation, ply + 1, -beta, -alpha);
+            if self.stopped {
+                break;
+            }
+            if value > best {
+                best = value;
+                if value > alpha {
+                    alpha = value;
+                    self.update_pv(ply, chess_move);
+                }
+                if value >= beta {
+                    break;
+                }
+            }
+        }
+        best
+    }
+
+    // The quiet move which caused a beta cutoff becomes a killer and the countermove of the previous move
+    // Its history score is raised and the scores of the quiet moves searched before it are lowered
+    fn update_quiet_heuristics(&mut self, chess_board: &ChessBoard, ply: usize, depth: i32, chess_move: ChessMove, searched: &[ChessMove]) {
+        let color = chess_board.inner.current_color;
+        let bonus = depth * depth;
+        self.killers[ply].add(chess_move);
+        if let Some(previous) = self.previous_moves[ply] {
+            self.countermoves.set(previous, chess_move);
+        }
+        self.history.update(color, chess_move, bonus);
+        for quiet in searched {
+            self.history.update(color, *quiet, -bonus);
+        }
+    }
+
+    // The principal variation of "ply" is the best move followed by the principal variation of the next ply
+    fn update_pv(&mut self, ply: usize, chess_move: ChessMove) {
+        let (pv, child_pv) = self.pv.split_at_mut(ply + 1);
+        pv[ply].clear();
+        pv[ply].push(chess_move);
+        pv[ply].extend_from_slice(&child_pv[0]);
+    }
+}
+
+pub(crate) fn legal_moves(chess_board: &ChessBoard) -> Vec<ChessMove> {
+    if chess_board.is_standard() { chess_board.standard_legal_moves() } else { chess_board.legal_moves() }
+}
+
+fn make_move(chess_board: &ChessBoard, chess_move: ChessMove) -> ChessBoard {
+    if chess_board.is_standard() {
+        chess_board.make_move_unchecked(chess_move)
+    } else {
+        chess_board.make_move(chess_move).unwrap().0
+    }
+}
+
+// Passes the move to the opponent, the position after it can't be repeated from before it
+fn make_null_move(chess_board: &ChessBoard) -> ChessBoard {
+    let mut chess_board = chess_board.clone();
+    chess_board.inner.en_passant_mask = 0;
+    chess_board.inner.half_moves = 0;
+    chess_board.inner.toggle_current_color();
+    chess_board
+}
+
+fn static_value(chess_board: &ChessBoard, evaluation: Evaluation) -> i32 {
+    evaluation.score(chess_board.inner.current_color) + TEMPO
+}
+
+// The value of a position the variant has ended, a win is scored as a mate on the current ply
+fn outcome_value(chess_board: &ChessBoard, ply: usize) -> Option<i32> {
+    match chess_board.variant().outcome(chess_board)? {
+        GameState::Win(color) if color == chess_board.inner.current_color => Some(MATE_SCORE - ply as i32),
+        GameState::Win(_) => Some(-MATE_SCORE + ply as i32),
+        _ => Some(0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn iteration(depth: u32, value: i32) -> Option<Iteration> {
+        Some(Iteration { depth, lines: vec![Line { value, pv: vec![] }] })
+    }
+
+    #[test]
+    fn test_deepest_iteration() {
+        // A helper with a better score at the same depth doesn't replace the main thread
+        let best = deepest_iteration(iteration(5, 10), [iteration(5, 200), iteration(4, 300)].into_iter()).unwrap();
+        assert_eq!((best.depth, best.lines[0].value), (5, 10));
+
+        let best = deepest_iteration(iteration(5, 10), [iteration(6, -50), iteration(6, 100), None].into_iter()).unwrap();
+        assert_eq!((best.depth, best.lines[0].value), (6, -50));
+
+        let best = deepest_iteration(None, [None, iteration(2, 0)].into_iter()).unwrap();
+        assert_eq!(best.depth, 2);
+    }
+}
@@ -16,7 +16,7 @@ impl<'a> Square<'a> {
     /// Returns if square should be light or dark
     /// For example white king start position should always be on dark square
     pub fn dark_color(&self) -> bool {
-        self.as_index().get() % 2 != 0 
+        !self.as_index().get().is_multiple_of(2) 
     }
 
     /// Returns the piece type on the square
@@ -45,7 +45,7 @@ impl<'a> Square<'a> {
         Index::new(self.rank.get() * BOARD_FILES + self.file.get()).unwrap()
     } 
 
-    /// Returns all legal moves as a vector
+    /// Returns all legal moves as a vector, following the rules of the variant
     /// Returns an empty vector if there are no legal moves
     /// Returns None if the selected piece does not contain a piece which the current side can move
     pub fn get_moves(&self) -> Option<Vec<Move<'a>>> {
@@ -54,19 +54,30 @@ impl<'a> Square<'a> {
             return None;
         }
 
-        let piece_type = self.chess_board.inner.get_piece_type(bb_square);
-        let mut bb_moves: BitBoard = get_move_generator(piece_type)(&self.chess_board.inner, self.as_index().get());
-
         let mut moves: Vec<Move<'a>> = vec![];
-        while bb_moves != 0 {
-            let index: usize = pop_lsb(&mut bb_moves);
-            assert!(index < 64);
-            
-            moves.push(Move{
-                src: self.as_index(),
-                dst: Index::new(index).unwrap(),
-                chess_board: self.chess_board
-            });
+        if self.chess_board.is_standard() {
+            let piece_type = self.chess_board.inner.get_piece_type(bb_square);
+            let mut bb_moves: BitBoard = get_move_generator(piece_type)(&self.chess_board.inner, &LegalMasks::new(&self.chess_board.inner), self.as_index().get());
+            while bb_moves != 0 {
+                let index: usize = pop_lsb(&mut bb_moves);
+                assert!(index < 64);
+
+                moves.push(Move{
+                    src: self.as_index(),
+                    dst: self.chess_board.to_public_dst(self.as_index(), Index::new(index).unwrap()),
+                    chess_board: self.chess_board
+                });
+            }
+            return Some(moves);
+        }
+
+        // The variants only generate the full move list, a promotion is one move and the piece type is chosen with the PawnPromotionResolver
+        let mut dsts: BitBoard = 0;
+        for chess_move in self.chess_board.legal_moves() {
+            if chess_move.src == self.as_index() && dsts & chess_move.dst.as_bb() == 0 {
+                dsts |= chess_move.dst.as_bb();
+                moves.push(Move{ src: chess_move.src, dst: chess_move.dst, chess_board: self.chess_board });
+            }
         }
         Some(moves)
     }
@@ -0,0 +1,166 @@
+use crate::chess_board::*;
+use crate::types::*;
+
+use crate::core::board::*;
+
+/// The material a player gives up in an odds game
+/// The knight and the rook are the ones on the queen side, in pawn and move the f-pawn is given up and the opponent moves first
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Odds {
+    Knight,
+    Rook,
+    Queen,
+    PawnAndMove,
+}
+
+/// Builds a start position from the standard setup by removing and adding pieces, for example for odds games
+/// The castling rights are derived from the pieces, a color can castle with the rooks on a1 and h1 (a8 and h8) if its king is on e1 (e8)
+#[derive(Debug, Clone)]
+pub struct StartPosition {
+    pieces: [Option<(PieceType, PieceColor)>; BOARD_SIZE],
+    side_to_move: PieceColor,
+}
+
+impl Default for StartPosition {
+    fn default() -> Self {
+        StartPosition::new()
+    }
+}
+
+impl StartPosition {
+    const BACK_RANK: [PieceType; BOARD_FILES] = [ PieceType::Rook, PieceType::Knight, PieceType::Bishop, PieceType::King,
+                                                  PieceType::Queen, PieceType::Bishop, PieceType::Knight, PieceType::Rook ];
+
+    /// Returns the standard start position with white to move
+    pub fn new() -> Self {
+        let mut start_position = StartPosition::empty();
+        for file in 0..BOARD_FILES {
+            start_position.pieces[square_index(0, file)] = Some((StartPosition::BACK_RANK[file], PieceColor::White));
+            start_position.pieces[square_index(1, file)] = Some((PieceType::Pawn, PieceColor::White));
+            start_position.pieces[square_index(BOARD_RANKS - 2, file)] = Some((PieceType::Pawn, PieceColor::Black));
+            start_position.pieces[square_index(BOARD_RANKS - 1, file)] = Some((StartPosition::BACK_RANK[file], PieceColor::Black));
+        }
+        start_position
+    }
+
+    /// Returns an empty board with white to move
+    pub fn empty() -> Self {
+        StartPosition { pieces: [None; BOARD_SIZE], side_to_move: PieceColor::White }
+    }
+
+    /// Returns the standard start position where "color" gives the odds
+    pub fn odds(odds: Odds, color: PieceColor) -> Self {
+        let rank = match color {
+            PieceColor::White => 0,
+            PieceColor::Black => BOARD_RANKS - 1,
+        };
+        // Files count from the h-file
+        let square = match odds {
+            Odds::Knight => square_index(rank, 6),
+            Odds::Rook => square_index(rank, 7),
+            Odds::Queen => square_index(rank, 4),
+            Odds::PawnAndMove => square_index(if color == PieceColor::White { 1 } else { BOARD_RANKS - 2 }, 2),
+        };
+        let start_position = StartPosition::new().remove(Index::new(square).unwrap());
+        match odds {
+            Odds::PawnAndMove => start_position.side_to_move(PieceColor::opposite(color)),
+            _ => start_position,
+        }
+    }
+
+    /// Removes the piece on "square", if there is one
+    pub fn remove(mut self, square: Index) -> Self {
+        self.pieces[square.get()] = None;
+        self
+    }
+
+    /// Places a piece on "square", replacing the piece which was there
+    pub fn add(mut self, square: Index, piece_type: PieceType, color: PieceColor) -> Self {
+        self.pieces[square.get()] = Some((piece_type, color));
+        self
+    }
+
+    /// Sets the player who makes the first move
+    pub fn side_to_move(mut self, color: PieceColor) -> Self {
+        self.side_to_move = color;
+        self
+    }
+
+    /// Returns the piece on "square"
+    pub fn piece(&self, square: Index) -> Option<(PieceType, PieceColor)> {
+        self.pieces[square.get()]
+    }
+
+    /// Returns the chess board of the position
+    /// Returns None if a color doesn't have exactly one king, if a pawn is on the first or last rank, or if the player who doesn't move is in check
+    pub fn build(&self) -> Option<ChessBoard> {
+        for color in [PieceColor::White, PieceColor::Black] {
+            if self.pieces.iter().filter(|piece| **piece == Some((PieceType::King, color))).count() != 1 {
+                return None;
+            }
+        }
+        let pawn_on_promotion_rank = self.pieces.iter().enumerate()
+            .any(|(square, piece)| matches!(piece, Some((PieceType::Pawn, _))) && ((1 as BitBoard) << square) & PROMOTION_RANKS != 0);
+        if pawn_on_promotion_rank {
+            return None;
+        }
+
+        let chess_board = ChessBoard::new(Some(&self.fen()))?;
+        let mut inner = chess_board.inner.clone();
+        inner.toggle_current_color();
+        if inner.is_current_player_in_check() {
+            return None;
+        }
+        Some(chess_board)
+    }
+
+    fn fen(&self) -> String {
+        let mut placement = String::new();
+        for rank in (0..BOARD_RANKS).rev() {
+            let mut empty = 0;
+            for file in (0..BOARD_FILES).rev() {
+                let Some((piece_type, color)) = self.pieces[square_index(rank, file)] else {
+                    empty += 1;
+                    continue;
+                };
+                if empty != 0 {
+                    placement.push_str(&empty.to_string());
+                    empty = 0;
+                }
+                let letter = match piece_type {
+                    PieceType::Pawn => 'p',
+                    PieceType::Knight => 'n',
+                    PieceType::Bishop => 'b',
+                    PieceType::Rook => 'r',
+                    PieceType::Queen => 'q',
+                    PieceType::King => 'k',
+                };
+                placement.push(if color == PieceColor::White { letter.to_ascii_uppercase() } else { letter });
+            }
+            if empty != 0 {
+                placement.push_str(&empty.to_string());
+            }
+            if rank != 0 {
+                placement.push('/');
+            }
+        }
+
+        let mut castling = String::new();
+        for (color, rank, letters) in [ (PieceColor::White, 0, ['K', 'Q']), (PieceColor::Black, BOARD_RANKS - 1, ['k', 'q']) ] {
+            if self.pieces[square_index(rank, 3)] != Some((PieceType::King, color)) {
+                continue;
+            }
+            for (file, letter) in [ (0, letters[0]), (BOARD_FILES - 1, letters[1]) ] {
+                if self.pieces[square_index(rank, file)] == Some((PieceType::Rook, color)) {
+                    castling.push(letter);
+                }
+            }
+        }
+        if castling.is_empty() {
+            castling.push('-');
+        }
+
+        let turn = if self.side_to_move == PieceColor::White { 'w' } else { 'b' };
+        format!("{} {} {} - 0 1", placement, turn, castling)
+    }
+}
@@ -0,0 +1,226 @@
+// https://www.chessprogramming.org/Transposition_Table
+// https://www.chessprogramming.org/Shared_Hash_Table#Lockless
+use std::sync::atomic::{ AtomicU8, AtomicU64, Ordering };
+
+use crate::mv::*;
+use crate::search::*;
+use crate::types::*;
+
+use crate::core::piece::*;
+
+/// How the score of an entry bounds the real score of the position
+/// Lower if the search failed high, Upper if it failed low and Exact if the score is inside the window
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+/// An entry of the transposition table
+/// The score is from the view of the side to move, mates are counted from the ply the table was probed at
+/// Age is the search the entry was stored in, as counted by TranspositionTable::new_search
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct TranspositionEntry {
+    pub depth: u8,
+    pub bound: Bound,
+    pub score: i32,
+    pub best_move: Option<ChessMove>,
+    pub age: u8,
+}
+
+/// A fixed-size hash table of search results which several threads may use at the same time without locks
+/// Every bucket has a slot which keeps the deepest entry of the current search and a slot which is always replaced
+/// An entry is stored as the hash xor the data next to the data, so an entry torn by two threads writing at once is never returned
+pub struct TranspositionTable {
+    buckets: Vec<Bucket>,
+    age: AtomicU8,
+}
+
+// The depth-preferred slot and the always-replace slot
+struct Bucket {
+    slots: [Slot; 2],
+}
+
+struct Slot {
+    key: AtomicU64,
+    data: AtomicU64,    // The move in bits 0-15, the score in bits 16-31, the depth in bits 32-39, the bound in bits 40-41 and the age in bits 42-47
+}
+
+const AGE_MASK: u8 = 0x3F;
+
+impl TranspositionTable {
+    /// Returns an empty table of "size_mb" megabytes, with at least one bucket
+    pub fn new(size_mb: usize) -> Self {
+        let mut table = TranspositionTable { buckets: vec![], age: AtomicU8::new(0) };
+        table.resize(size_mb);
+        table
+    }
+
+    /// Replaces the table with an empty table of "size_mb" megabytes
+    pub fn resize(&mut self, size_mb: usize) {
+        let bucket_count = (size_mb * 1024 * 1024 / std::mem::size_of::<Bucket>()).max(1);
+        self.buckets = (0..bucket_count).map(|_| Bucket { slots: [Slot::new(), Slot::new()] }).collect();
+    }
+
+    /// Returns the size of the table in bytes
+    pub fn size(&self) -> usize {
+        self.buckets.len() * std::mem::size_of::<Bucket>()
+    }
+
+    /// Removes every entry
+    pub fn clear(&self) {
+        for slot in self.buckets.iter().flat_map(|bucket| &bucket.slots) {
+            slot.key.store(0, Ordering::Relaxed);
+            slot.data.store(0, Ordering::Relaxed);
+        }
+        self.age.store(0, Ordering::Relaxed);
+    }
+
+    /// Starts a new search, entries of earlier searches are replaced before deeper entries of the current search
+    pub fn new_search(&self) {
+        self.age.store(self.age.load(Ordering::Relaxed).wrapping_add(1) & AGE_MASK, Ordering::Relaxed);
+    }
+
+    /// Returns the age of the current search
+    pub fn age(&self) -> u8 {
+        self.age.load(Ordering::Relaxed)
+    }
+
+    /// Returns the entry of the position with "hash", with mate scores relative to "ply"
+    pub fn probe(&self, hash: u64, ply: usize) -> Option<TranspositionEntry> {
+        self.bucket(hash).slots.iter()
+            .map(|slot| (slot.key.load(Ordering::Relaxed), slot.data.load(Ordering::Relaxed)))
+            .find(|(key, data)| *data != 0 && key ^ data == hash)
+            .map(|(_, data)| {
+                let mut entry = decode(data);
+                entry.score = score_from_table(entry.score, ply);
+                entry
+            })
+    }
+
+    /// Stores the result of searching the position with "hash" at "ply" to "depth"
+    /// The depth-preferred slot is replaced by the same position, by a deeper or equally deep search and by any search after the one it was stored in
+    pub fn store(&self, hash: u64, ply: usize, depth: u8, bound: Bound, score: i32, best_move: Option<ChessMove>) {
+        let bucket = self.bucket(hash);
+        let age = self.age();
+
+        // A search which doesn't find a best move keeps the one stored before
+        let previous = self.probe(hash, ply);
+        let best_move = best_move.or(previous.and_then(|entry| entry.best_move));
+
+        let data = encode(TranspositionEntry { depth, bound, score: score_to_table(score, ply), best_move, age });
+        let preferred = &bucket.slots[0];
+        let preferred_data = preferred.data.load(Ordering::Relaxed);
+        let preferred_entry = decode(preferred_data);
+        let slot = if preferred_data == 0
+                || preferred.key.load(Ordering::Relaxed) ^ preferred_data == hash
+                || depth >= preferred_entry.depth
+                || preferred_entry.age != age {
+            preferred
+        } else {
+            &bucket.slots[1]
+        };
+        slot.key.store(hash ^ data, Ordering::Relaxed);
+        slot.data.store(data, Ordering::Relaxed);
+    }
+
+    /// Returns how full the table is in permille, counting the entries of the current search among the first thousand slots
+    pub fn hashfull(&self) -> usize {
+        let age = self.age();
+        self.buckets.iter().flat_map(|bucket| &bucket.slots).take(1000)
+            .filter(|slot| {
+                let data = slot.data.load(Ordering::Relaxed);
+                data != 0 && decode(data).age == age
+            })
+            .count() * 1000 / (self.buckets.len() * 2).min(1000)
+    }
+
+    fn bucket(&self, hash: u64) -> &Bucket {
+        &self.buckets[(hash % self.buckets.len() as u64) as usize]
+    }
+}
+
+impl Slot {
+    fn new() -> Self {
+        Slot { key: AtomicU64::new(0), data: AtomicU64::new(0) }
+    }
+}
+
+// A mate is stored as the distance from the position, and is probed as the distance from the root
+fn score_to_table(score: i32, ply: usize) -> i32 {
+    if score > MATE_SCORE - MAX_PLY as i32 {
+        score + ply as i32
+    } else if score < -MATE_SCORE + MAX_PLY as i32 {
+        score - ply as i32
+    } else {
+        score
+    }
+}
+
+fn score_from_table(score: i32, ply: usize) -> i32 {
+    if score > MATE_SCORE - MAX_PLY as i32 {
+        score - ply as i32
+    } else if score < -MATE_SCORE + MAX_PLY as i32 {
+        score + ply as i32
+    } else {
+        score
+    }
+}
+
+const PIECE_TYPES: [PieceType; 6] = [ PieceType::Pawn, PieceType::Knight, PieceType::Bishop, PieceType::Rook, PieceType::Queen, PieceType::King ];
+
+// The source in bits 0-5, the destination in bits 6-11, the promotion or dropped piece type plus one in bits 12-14 and whether it is a drop in bit 15
+// No move is 0, since no move goes from h1 to h1 and a drop has bit 15 set
+fn encode_move(chess_move: Option<ChessMove>) -> u64 {
+    let Some(chess_move) = chess_move else {
+        return 0;
+    };
+    let piece_type = chess_move.drop.or(chess_move.promotion).map_or(0, |piece_type| piece_type as u64 + 1);
+    chess_move.src.get() as u64 | (chess_move.dst.get() as u64) << 6 | piece_type << 12 | (chess_move.drop.is_some() as u64) << 15
+}
+
+fn decode_move(bits: u64) -> Option<ChessMove> {
+    if bits == 0 {
+        return None;
+    }
+    let src = Index::new((bits & 0x3F) as usize).unwrap();
+    let dst = Index::new((bits >> 6 & 0x3F) as usize).unwrap();
+    let piece_type = match bits >> 12 & 0x7 {
+        0 => None,
+        code => Some(PIECE_TYPES[code as usize - 1]),
+    };
+    if bits >> 15 & 1 == 1 {
+        Some(ChessMove::new_drop(piece_type.unwrap(), dst))
+    } else {
+        Some(ChessMove::new(src, dst, piece_type))
+    }
+}
+
+fn encode(entry: TranspositionEntry) -> u64 {
+    let bound = match entry.bound {
+        Bound::Exact => 1,
+        Bound::Lower => 2,
+        Bound::Upper => 3,
+    };
+    encode_move(entry.best_move)
+        | (entry.score as i16 as u16 as u64) << 16
+        | (entry.depth as u64) << 32
+        | bound << 40
+        | ((entry.age & AGE_MASK) as u64) << 42
+}
+
+fn decode(data: u64) -> TranspositionEntry {
+    let bound = match data >> 40 & 0x3 {
+        2 => Bound::Lower,
+        3 => Bound::Upper,
+        _ => Bound::Exact,
+    };
+    TranspositionEntry {
+        depth: (data >> 32 & 0xFF) as u8,
+        bound,
+        score: (data >> 16 & 0xFFFF) as u16 as i16 as i32,
+        best_move: decode_move(data & 0xFFFF),
+        age: (data >> 42) as u8 & AGE_MASK,
+    }
+}
@@ -1,7 +1,9 @@
+use std::fmt;
+
 use crate::core::board::*;
 use crate::core::board::{ BOARD_SIZE, BOARD_FILES, BOARD_RANKS };
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub struct BoundedUsize<const MAX: usize>(usize);
 
 // None inclusive bound
@@ -36,4 +38,23 @@ impl Index {
     pub fn get_file(&self) -> File {
         File::new(file_index(self.get())).unwrap()
     }
-}
\ No newline at end of file
+
+    /// Returns the index of a square in algebraic notation, for example "e4"
+    /// Returns None if the string is not a square
+    pub fn from_algebraic(square: &str) -> Option<Index> {
+        let &[file @ b'a'..=b'h', rank @ b'1'..=b'8'] = square.as_bytes() else {
+            return None;
+        };
+        Index::new(square_index((rank - b'1') as usize, (b'h' - file) as usize))
+    }
+}
+
+/// Formats the index in algebraic notation, for example "e4"
+impl fmt::Display for Index {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // File 0 is the h-file
+        let file = (b'h' - self.get_file().get() as u8) as char;
+        let rank = (b'1' + self.get_rank().get() as u8) as char;
+        write!(f, "{}{}", file, rank)
+    }
+}
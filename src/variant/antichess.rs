@@ -0,0 +1,76 @@
+use crate::variant::*;
+
+use crate::core::board::*;
+
+/// Antichess, a player wins by losing all their pieces or by being stalemated
+/// Capturing is compulsory, there is no check and no castling, and the king is an ordinary piece which can be captured and promoted to
+/// https://lichess.org/variant/antichess
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Antichess;
+
+impl Antichess {
+    const PROMOTION_PIECE_TYPES: [PieceType; 5] = [ PieceType::Knight, PieceType::Bishop, PieceType::Rook, PieceType::Queen, PieceType::King ];
+}
+
+impl Variant for Antichess {
+    fn name(&self) -> &'static str {
+        "antichess"
+    }
+
+    fn start_fen(&self) -> &'static str {
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1"
+    }
+
+    // The moves ignore the safety of the king, and if any of them captures only the captures are legal
+    fn legal_moves(&self, chess_board: &ChessBoard) -> Vec<ChessMove> {
+        let mut moves = chess_board.pseudo_legal_moves();
+        if moves.iter().any(|chess_move| chess_board.captured_piece(*chess_move).is_some()) {
+            moves.retain(|chess_move| chess_board.captured_piece(*chess_move).is_some());
+        }
+        moves
+    }
+
+    fn is_legal(&self, chess_board: &ChessBoard, chess_move: ChessMove) -> bool {
+        self.legal_moves(chess_board).contains(&chess_move)
+    }
+
+    fn promotion_piece_types(&self) -> &'static [PieceType] {
+        &Antichess::PROMOTION_PIECE_TYPES
+    }
+
+    fn is_check(&self, _chess_board: &ChessBoard) -> bool {
+        false
+    }
+
+    // A player who has lost all their pieces, or who has no legal moves, has won
+    fn outcome(&self, chess_board: &ChessBoard) -> Option<GameState> {
+        let inner = &chess_board.inner;
+        if let Some(color) = [PieceColor::White, PieceColor::Black].into_iter().find(|color| inner.all_pieces[*color as usize] == 0) {
+            return Some(GameState::Win(color));
+        }
+        if self.legal_moves(chess_board).is_empty() {
+            return Some(GameState::Win(inner.current_color));
+        }
+        None
+    }
+
+    // "color" can't win once the opponent has won, or if only bishops are left and the opponent can't capture its bishops
+    fn has_mating_material(&self, chess_board: &ChessBoard, color: PieceColor) -> bool {
+        let inner = &chess_board.inner;
+        let own_pieces = inner.all_pieces[color as usize];
+        let enemy_pieces = inner.all_pieces[PieceColor::opposite(color) as usize];
+        if own_pieces == 0 {
+            return true;
+        }
+        if enemy_pieces == 0 {
+            return false;
+        }
+        if inner.all_pieces() == inner.pieces[PieceType::Bishop as usize] {
+            // A bishop can only be captured by a bishop on the squares of the same color
+            let uncapturable_on_dark = own_pieces & DARK_SQUARES != 0 && enemy_pieces & DARK_SQUARES == 0;
+            let uncapturable_on_light = own_pieces & !DARK_SQUARES != 0 && enemy_pieces & !DARK_SQUARES == 0;
+            return !uncapturable_on_dark && !uncapturable_on_light;
+        }
+        true
+    }
+}
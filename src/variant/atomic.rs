@@ -0,0 +1,153 @@
+use crate::types::*;
+use crate::variant::*;
+
+use crate::core::board::*;
+use crate::core::chess_board as internal;
+use crate::core::move_generation::*;
+use crate::core::precompute_masks::*;
+
+/// Atomic, every capture explodes the capturing piece and every piece except pawns next to the capture square
+/// A player wins by exploding the enemy king, kings can't capture and kings next to each other are not in check
+/// https://lichess.org/variant/atomic
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Atomic;
+
+impl Atomic {
+    // Removes the capturing piece on "square" and the pieces around it which aren't pawns
+    fn explode(inner: &mut internal::ChessBoard, square: usize) {
+        let around = BBMASKS.pieces.attacks[PieceColor::White as usize][PieceType::King as usize][square];
+        inner.remove_pieces(((1 as BitBoard) << square) | (around & !inner.pieces[PieceType::Pawn as usize]));
+    }
+
+    fn king_square(inner: &internal::ChessBoard, color: PieceColor) -> Option<usize> {
+        let bb_king = inner.pieces[PieceType::King as usize] & inner.all_pieces[color as usize];
+        (bb_king != 0).then(|| bb_king.trailing_zeros() as usize)
+    }
+
+    // A king is attacked if the enemy king exists and isn't next to it, and a piece other than the enemy king attacks it
+    fn is_attacked(inner: &internal::ChessBoard, color: PieceColor) -> bool {
+        let (Some(king_square), Some(enemy_king_square)) = (Atomic::king_square(inner, color), Atomic::king_square(inner, PieceColor::opposite(color))) else {
+            return false;
+        };
+        if BBMASKS.pieces.attacks[color as usize][PieceType::King as usize][king_square] & ((1 as BitBoard) << enemy_king_square) != 0 {
+            return false;
+        }
+        get_pieces_attacking_square(inner, king_square, PieceColor::opposite(color), inner.all_pieces()) & !inner.pieces[PieceType::King as usize] != 0
+    }
+
+    // Moves by the rules of Atomic which may leave the own king exploded or attacked
+    fn pseudo_legal_moves(chess_board: &ChessBoard) -> Vec<ChessMove> {
+        let inner = &chess_board.inner;
+        let enemy_pieces = inner.all_pieces[PieceColor::opposite(inner.current_color) as usize];
+        let mut moves = chess_board.pseudo_legal_moves();
+        moves.retain(|chess_move| inner.pieces[PieceType::King as usize] & chess_move.src.as_bb() == 0 || enemy_pieces & chess_move.dst.as_bb() == 0);
+
+        // The king may castle onto and through the attacked squares next to the enemy king, since it can't be captured there
+        if let Some(king_square) = Atomic::king_square(inner, inner.current_color)
+                && !Atomic::is_attacked(inner, inner.current_color) {
+            let safe = match Atomic::king_square(inner, PieceColor::opposite(inner.current_color)) {
+                Some(enemy_king_square) => BBMASKS.pieces.attacks[PieceColor::White as usize][PieceType::King as usize][enemy_king_square],
+                None => 0,
+            };
+            let mut castling_moves = get_castling_moves(inner, king_square, safe);
+            let src = Index::new(king_square).unwrap();
+            while castling_moves != 0 {
+                let rook_square = Index::new(pop_lsb(&mut castling_moves)).unwrap();
+                moves.push(ChessMove::new(src, chess_board.to_public_dst(src, rook_square), None));
+            }
+        }
+        moves
+    }
+
+    // The own king has to survive the move, and it may only be attacked afterwards if the enemy king exploded
+    fn is_safe(chess_board: &ChessBoard, chess_move: ChessMove) -> bool {
+        let color = chess_board.inner.current_color;
+        let mut inner = chess_board.inner.clone();
+        let is_capture = chess_board.captured_piece(chess_move).is_some();
+        inner.make_move(chess_move.src.get(), chess_board.to_internal_dst(chess_move.src, chess_move.dst).as_bb());
+        if let Some(promotion) = chess_move.promotion {
+            inner.resolve_promotion(promotion);
+        }
+        if is_capture {
+            Atomic::explode(&mut inner, chess_move.dst.get());
+        }
+
+        if Atomic::king_square(&inner, color).is_none() {
+            return false;
+        }
+        Atomic::king_square(&inner, PieceColor::opposite(color)).is_none() || !Atomic::is_attacked(&inner, color)
+    }
+}
+
+impl Variant for Atomic {
+    fn name(&self) -> &'static str {
+        "atomic"
+    }
+
+    fn legal_moves(&self, chess_board: &ChessBoard) -> Vec<ChessMove> {
+        let mut moves = Atomic::pseudo_legal_moves(chess_board);
+        moves.retain(|chess_move| Atomic::is_safe(chess_board, *chess_move));
+        moves
+    }
+
+    fn is_legal(&self, chess_board: &ChessBoard, chess_move: ChessMove) -> bool {
+        Atomic::pseudo_legal_moves(chess_board).contains(&chess_move) && Atomic::is_safe(chess_board, chess_move)
+    }
+
+    fn after_move(&self, previous: &ChessBoard, chess_board: &mut ChessBoard, chess_move: ChessMove) {
+        if previous.captured_piece(chess_move).is_some() {
+            Atomic::explode(&mut chess_board.inner, chess_move.dst.get());
+        }
+    }
+
+    fn is_check(&self, chess_board: &ChessBoard) -> bool {
+        Atomic::is_attacked(&chess_board.inner, chess_board.inner.current_color)
+    }
+
+    fn outcome(&self, chess_board: &ChessBoard) -> Option<GameState> {
+        [PieceColor::White, PieceColor::Black].into_iter()
+            .find(|color| Atomic::king_square(&chess_board.inner, *color).is_none())
+            .map(|color| GameState::Win(PieceColor::opposite(color)))
+    }
+
+    // Follows python-chess, a bare king can't win, and neither can a single minor piece or rook against a bare king
+    fn has_mating_material(&self, chess_board: &ChessBoard, color: PieceColor) -> bool {
+        let inner = &chess_board.inner;
+        let kings = inner.pieces[PieceType::King as usize];
+        let own_pieces = inner.all_pieces[color as usize];
+        let enemy_pieces = inner.all_pieces[PieceColor::opposite(color) as usize];
+        if enemy_pieces & kings == 0 {
+            return true;
+        }
+        if own_pieces & !kings == 0 {
+            return false;
+        }
+
+        // The enemy pieces may explode next to the enemy king, unless only bishops which can't capture each other are left
+        if enemy_pieces & !kings != 0 {
+            let bishops = inner.pieces[PieceType::Bishop as usize];
+            if inner.all_pieces() == bishops | kings {
+                let white_bishops = bishops & inner.all_pieces[PieceColor::White as usize];
+                let black_bishops = bishops & inner.all_pieces[PieceColor::Black as usize];
+                let same_color_possible = (white_bishops & DARK_SQUARES != 0 && black_bishops & DARK_SQUARES != 0)
+                    || (white_bishops & !DARK_SQUARES != 0 && black_bishops & !DARK_SQUARES != 0);
+                return same_color_possible;
+            }
+            return true;
+        }
+
+        if inner.pieces[PieceType::Queen as usize] | inner.pieces[PieceType::Pawn as usize] != 0 {
+            return true;
+        }
+        let knights = inner.pieces[PieceType::Knight as usize];
+        let pieces = knights | inner.pieces[PieceType::Bishop as usize] | inner.pieces[PieceType::Rook as usize];
+        if pieces.count_ones() == 1 {
+            return false;
+        }
+        // Two knights can't mate a bare king
+        if inner.all_pieces() == knights | kings {
+            return knights.count_ones() > 2;
+        }
+        true
+    }
+}
@@ -0,0 +1,158 @@
+use crate::types::*;
+use crate::variant::*;
+
+use crate::core::board::*;
+use crate::core::move_generation::*;
+
+/// Crazyhouse, a captured piece goes to the pocket of the capturing player, who may drop it on an empty square instead of moving
+/// A captured piece which was promoted goes to the pocket as a pawn
+/// The pockets are part of the FEN string in brackets after the placement, for example "[Nq]", and "~" marks a promoted piece
+/// https://lichess.org/variant/crazyhouse
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Crazyhouse;
+
+/// The rules of a single board of Bughouse, which are the rules of Crazyhouse except that captured pieces go to the partner
+/// BughouseBoards plays the two boards of a game
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Bughouse;
+
+// Every drop of a piece in the pocket of the current player on an empty square, pawns can't be dropped on the first and last rank
+// Dropping a piece never exposes the own king, so the drop only has to block a check, it can't block a double check
+fn drop_moves(chess_board: &ChessBoard) -> Vec<ChessMove> {
+    let inner = &chess_board.inner;
+    let pocket = inner.pockets[inner.current_color as usize];
+    if pocket.is_empty() {
+        return vec![];
+    }
+    let targets = !inner.all_pieces() & LegalMasks::new(inner).check_mask();
+
+    let mut moves = vec![];
+    for piece_type in [PieceType::Pawn, PieceType::Knight, PieceType::Bishop, PieceType::Rook, PieceType::Queen] {
+        if pocket.count(piece_type) == 0 {
+            continue;
+        }
+        let mut squares = if piece_type == PieceType::Pawn { targets & !PROMOTION_RANKS } else { targets };
+        while squares != 0 {
+            moves.push(ChessMove::new_drop(piece_type, Index::new(pop_lsb(&mut squares)).unwrap()));
+        }
+    }
+    moves
+}
+
+fn is_legal_with_drops(chess_board: &ChessBoard, chess_move: ChessMove) -> bool {
+    match chess_move.drop {
+        Some(_) => drop_moves(chess_board).contains(&chess_move),
+        None => chess_board.is_standard_legal(chess_move),
+    }
+}
+
+// Returns the piece the move captures as it goes to a pocket, a promoted piece is a pawn again
+fn pocketed_piece(previous: &ChessBoard, chess_move: ChessMove) -> Option<PieceType> {
+    let captured = previous.captured_piece(chess_move)?;
+    if previous.inner.promoted & chess_move.dst.as_bb() != 0 {
+        Some(PieceType::Pawn)
+    } else {
+        Some(captured)
+    }
+}
+
+// Captured pieces can return to the board, so any piece on the board or in a pocket may still checkmate
+fn has_material_with_drops(chess_board: &ChessBoard) -> bool {
+    let inner = &chess_board.inner;
+    inner.all_pieces() & !inner.pieces[PieceType::King as usize] != 0
+        || inner.pockets.iter().any(|pocket| !pocket.is_empty())
+}
+
+impl Variant for Crazyhouse {
+    fn name(&self) -> &'static str {
+        "crazyhouse"
+    }
+
+    fn legal_moves(&self, chess_board: &ChessBoard) -> Vec<ChessMove> {
+        let mut moves = chess_board.standard_legal_moves();
+        moves.extend(drop_moves(chess_board));
+        moves
+    }
+
+    fn is_legal(&self, chess_board: &ChessBoard, chess_move: ChessMove) -> bool {
+        is_legal_with_drops(chess_board, chess_move)
+    }
+
+    fn after_move(&self, previous: &ChessBoard, chess_board: &mut ChessBoard, chess_move: ChessMove) {
+        if let Some(piece_type) = pocketed_piece(previous, chess_move) {
+            chess_board.inner.add_to_pocket(previous.inner.current_color, piece_type);
+        }
+    }
+
+    fn has_mating_material(&self, chess_board: &ChessBoard, _color: PieceColor) -> bool {
+        has_material_with_drops(chess_board)
+    }
+}
+
+impl Variant for Bughouse {
+    fn name(&self) -> &'static str {
+        "bughouse"
+    }
+
+    fn legal_moves(&self, chess_board: &ChessBoard) -> Vec<ChessMove> {
+        let mut moves = chess_board.standard_legal_moves();
+        moves.extend(drop_moves(chess_board));
+        moves
+    }
+
+    fn is_legal(&self, chess_board: &ChessBoard, chess_move: ChessMove) -> bool {
+        is_legal_with_drops(chess_board, chess_move)
+    }
+
+    // The partner may pass on pieces at any time, so no position of a single board lacks material
+    fn has_mating_material(&self, _chess_board: &ChessBoard, _color: PieceColor) -> bool {
+        true
+    }
+}
+
+/// The two boards of a game of Bughouse
+/// The partner of the white player of one board plays black on the other, and receives every piece the white player captures
+#[derive(Debug, Clone)]
+pub struct BughouseBoards {
+    boards: [ChessBoard; 2],
+}
+
+impl Default for BughouseBoards {
+    fn default() -> Self {
+        BughouseBoards::new()
+    }
+}
+
+impl BughouseBoards {
+    /// Returns two boards in the start position
+    pub fn new() -> Self {
+        let board = ChessBoard::with_variant(&Bughouse, None).unwrap();
+        BughouseBoards { boards: [board.clone(), board] }
+    }
+
+    /// Returns the two boards from FEN strings, returns None if either of them is invalid
+    pub fn from_fens(fens: [&str; 2]) -> Option<Self> {
+        Some(BughouseBoards {
+            boards: [ ChessBoard::with_variant(&Bughouse, Some(fens[0]))?, ChessBoard::with_variant(&Bughouse, Some(fens[1]))? ],
+        })
+    }
+
+    /// Returns board 0 or 1
+    pub fn board(&self, index: usize) -> &ChessBoard {
+        &self.boards[index]
+    }
+
+    /// Makes the move on board 0 or 1 and passes the captured piece to the pocket of the partner on the other board
+    /// Returns None if the move is illegal
+    pub fn make_move(&mut self, index: usize, chess_move: ChessMove) -> Option<MoveType> {
+        let board = &self.boards[index];
+        let captured_color = PieceColor::opposite(board.inner.current_color);
+        let pocketed = pocketed_piece(board, chess_move);
+        let (board, move_type) = board.make_move(chess_move)?;
+        self.boards[index] = board;
+        if let Some(piece_type) = pocketed {
+            self.boards[1 - index].inner.add_to_pocket(captured_color, piece_type);
+        }
+        Some(move_type)
+    }
+}
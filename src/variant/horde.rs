@@ -0,0 +1,65 @@
+use crate::types::*;
+use crate::variant::*;
+
+use crate::core::board::*;
+
+/// Horde, white has 36 pawns and no king, and black wins by capturing all of them
+/// White pawns on the first rank may move two squares, which can't be captured en passant
+/// https://lichess.org/variant/horde
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Horde;
+
+impl Horde {
+    const FIRST_RANK: BitBoard = 0x00000000000000FF;
+
+    // White has no king, so every double move of a pawn on the first rank which isn't blocked is legal
+    fn first_rank_double_moves(chess_board: &ChessBoard) -> Vec<ChessMove> {
+        let inner = &chess_board.inner;
+        if inner.current_color != PieceColor::White {
+            return vec![];
+        }
+        let white = inner.all_pieces[PieceColor::White as usize];
+        if inner.pieces[PieceType::King as usize] & white != 0 {
+            return vec![];
+        }
+        let mut pawns = inner.pieces[PieceType::Pawn as usize] & white & Horde::FIRST_RANK;
+
+        let mut moves = vec![];
+        while pawns != 0 {
+            let src = pop_lsb(&mut pawns);
+            let path = ((1 as BitBoard) << (src + BOARD_FILES)) | ((1 as BitBoard) << (src + 2 * BOARD_FILES));
+            if path & inner.all_pieces() == 0 {
+                let dst = Index::new(src + 2 * BOARD_FILES).unwrap();
+                moves.push(ChessMove::new(Index::new(src).unwrap(), dst, None));
+            }
+        }
+        moves
+    }
+}
+
+impl Variant for Horde {
+    fn name(&self) -> &'static str {
+        "horde"
+    }
+
+    fn start_fen(&self) -> &'static str {
+        "rnbqkbnr/pppppppp/8/1PP2PP1/PPPPPPPP/PPPPPPPP/PPPPPPPP/PPPPPPPP w kq - 0 1"
+    }
+
+    fn legal_moves(&self, chess_board: &ChessBoard) -> Vec<ChessMove> {
+        let mut moves = chess_board.standard_legal_moves();
+        moves.extend(Horde::first_rank_double_moves(chess_board));
+        moves
+    }
+
+    fn is_legal(&self, chess_board: &ChessBoard, chess_move: ChessMove) -> bool {
+        chess_board.is_standard_legal(chess_move) || Horde::first_rank_double_moves(chess_board).contains(&chess_move)
+    }
+
+    fn outcome(&self, chess_board: &ChessBoard) -> Option<GameState> {
+        if chess_board.inner.all_pieces[PieceColor::White as usize] == 0 {
+            return Some(GameState::Win(PieceColor::Black));
+        }
+        None
+    }
+}
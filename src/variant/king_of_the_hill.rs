@@ -0,0 +1,30 @@
+use crate::core::board::*;
+use crate::variant::*;
+
+/// King of the Hill, a player also wins by moving their king to one of the four center squares
+/// https://lichess.org/variant/kingOfTheHill
+#[derive(Debug, Clone, Copy, Default)]
+pub struct KingOfTheHill;
+
+impl KingOfTheHill {
+    // d4, e4, d5 and e5
+    const HILL: BitBoard = 0x0000001818000000;
+}
+
+impl Variant for KingOfTheHill {
+    fn name(&self) -> &'static str {
+        "kingofthehill"
+    }
+
+    fn outcome(&self, chess_board: &ChessBoard) -> Option<GameState> {
+        let kings = chess_board.inner.pieces[PieceType::King as usize] & KingOfTheHill::HILL;
+        [PieceColor::White, PieceColor::Black].into_iter()
+            .find(|color| kings & chess_board.inner.all_pieces[*color as usize] != 0)
+            .map(GameState::Win)
+    }
+
+    // A lone king can still walk to the center
+    fn has_mating_material(&self, _chess_board: &ChessBoard, _color: PieceColor) -> bool {
+        true
+    }
+}
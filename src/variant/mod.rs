@@ -0,0 +1,94 @@
+// https://www.chessprogramming.org/Chess_Variants
+use std::any::Any;
+use std::fmt;
+
+use crate::chess_board::*;
+use crate::mv::*;
+use crate::promotion::*;
+
+pub mod antichess;
+pub mod atomic;
+pub mod crazyhouse;
+pub mod horde;
+pub mod king_of_the_hill;
+pub mod racing_kings;
+pub mod three_check;
+
+pub use antichess::*;
+pub use atomic::*;
+pub use crazyhouse::*;
+pub use horde::*;
+pub use king_of_the_hill::*;
+pub use racing_kings::*;
+pub use three_check::*;
+
+/// The rules of a chess variant, on top of the rules of standard chess
+/// Every method has a default which follows the rules of standard chess, so a variant only overrides the rules it changes
+/// A ChessBoard refers to its variant with a static reference, for example ChessBoard::with_variant(&KingOfTheHill, None)
+pub trait Variant: Any + fmt::Debug + Send + Sync {
+    /// The name of the variant as used by lichess and the UCI_Variant option, for example "kingofthehill"
+    fn name(&self) -> &'static str;
+
+    /// The FEN string of the start position
+    fn start_fen(&self) -> &'static str {
+        ChessBoard::INITIAL_POSITION_FEN
+    }
+
+    /// Returns false for a legal move of standard chess which the variant does not allow, for example giving check in Racing Kings
+    fn allows_move(&self, _chess_board: &ChessBoard, _chess_move: ChessMove) -> bool {
+        true
+    }
+
+    /// Returns all legal moves of the current player, by default the legal moves of standard chess which the variant allows
+    /// A variant with moves which standard chess does not have should also override is_legal
+    fn legal_moves(&self, chess_board: &ChessBoard) -> Vec<ChessMove> {
+        let mut moves = chess_board.standard_legal_moves();
+        moves.retain(|chess_move| self.allows_move(chess_board, *chess_move));
+        moves
+    }
+
+    /// Returns true if the move is one of the legal moves of the current player
+    fn is_legal(&self, chess_board: &ChessBoard, chess_move: ChessMove) -> bool {
+        chess_board.is_standard_legal(chess_move) && self.allows_move(chess_board, chess_move)
+    }
+
+    /// The piece types a pawn can be promoted to
+    fn promotion_piece_types(&self) -> &'static [PieceType] {
+        &PROMOTION_PIECE_TYPES
+    }
+
+    /// Returns true if the current player is in check, which decides between checkmate and stalemate without legal moves
+    fn is_check(&self, chess_board: &ChessBoard) -> bool {
+        chess_board.inner.is_current_player_in_check()
+    }
+
+    /// Updates the position after "chess_move" has been made on "previous", for example by counting the checks in Three-check
+    fn after_move(&self, _previous: &ChessBoard, _chess_board: &mut ChessBoard, _chess_move: ChessMove) {}
+
+    /// Returns the state of the game if the variant has ended it, before checkmate and stalemate are considered
+    /// The current player has no legal moves in a position the variant has ended
+    fn outcome(&self, _chess_board: &ChessBoard) -> Option<GameState> {
+        None
+    }
+
+    /// Returns false if "color" can't win by any series of legal moves
+    fn has_mating_material(&self, chess_board: &ChessBoard, color: PieceColor) -> bool {
+        chess_board.has_standard_mating_material(color)
+    }
+}
+
+/// Standard chess, the variant every ChessBoard uses unless another one is given
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Standard;
+
+impl Variant for Standard {
+    fn name(&self) -> &'static str {
+        "chess"
+    }
+}
+
+/// Returns the variant with the given name, as returned by Variant::name
+pub fn variant_by_name(name: &str) -> Option<&'static dyn Variant> {
+    let variants: [&'static dyn Variant; 9] = [ &Standard, &KingOfTheHill, &ThreeCheck, &RacingKings, &Horde, &Crazyhouse, &Bughouse, &Atomic, &Antichess ];
+    variants.into_iter().find(|variant| variant.name() == name)
+}
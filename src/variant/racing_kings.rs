@@ -0,0 +1,62 @@
+use crate::variant::*;
+
+use crate::core::board::*;
+use crate::core::move_generation::*;
+use crate::core::precompute_masks::*;
+
+/// Racing Kings, the first player to move their king to the eighth rank wins and giving check is not allowed
+/// If white reaches the eighth rank first, black gets one more move to reach it too, which draws the game
+/// https://lichess.org/variant/racingKings
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RacingKings;
+
+impl RacingKings {
+    const GOAL: BitBoard = 0xFF00000000000000;
+
+    // Returns true if the black king can move to a square on the eighth rank which is not attacked
+    fn can_black_reach_goal(chess_board: &ChessBoard) -> bool {
+        let inner = &chess_board.inner;
+        let black = inner.all_pieces[PieceColor::Black as usize];
+        let king_square = (inner.pieces[PieceType::King as usize] & black).trailing_zeros() as usize;
+        let mut targets = BBMASKS.pieces.attacks[PieceColor::Black as usize][PieceType::King as usize][king_square] & RacingKings::GOAL & !black;
+        while targets != 0 {
+            let target = pop_lsb(&mut targets);
+            if get_pieces_attacking_square(inner, target, PieceColor::White, inner.all_pieces()) == 0 {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+impl Variant for RacingKings {
+    fn name(&self) -> &'static str {
+        "racingkings"
+    }
+
+    fn start_fen(&self) -> &'static str {
+        "8/8/8/8/8/8/krbnNBRK/qrbnNBRQ w - - 0 1"
+    }
+
+    fn allows_move(&self, chess_board: &ChessBoard, chess_move: ChessMove) -> bool {
+        !chess_board.gives_check(chess_move)
+    }
+
+    fn outcome(&self, chess_board: &ChessBoard) -> Option<GameState> {
+        let kings = chess_board.inner.pieces[PieceType::King as usize] & RacingKings::GOAL;
+        let white = kings & chess_board.inner.all_pieces[PieceColor::White as usize] != 0;
+        let black = kings & chess_board.inner.all_pieces[PieceColor::Black as usize] != 0;
+        match (white, black) {
+            (false, false) => None,
+            (true, true) => Some(GameState::Draw),
+            (false, true) => Some(GameState::Win(PieceColor::Black)),
+            (true, false) if chess_board.inner.current_color == PieceColor::Black && RacingKings::can_black_reach_goal(chess_board) => None,
+            (true, false) => Some(GameState::Win(PieceColor::White)),
+        }
+    }
+
+    // Both kings can always race
+    fn has_mating_material(&self, _chess_board: &ChessBoard, _color: PieceColor) -> bool {
+        true
+    }
+}
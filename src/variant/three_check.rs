@@ -0,0 +1,34 @@
+use crate::variant::*;
+
+/// Three-check, a player also wins by giving check for the third time
+/// The checks given by both players are part of the FEN string as a seventh field, for example "+2+0" when white has given two checks
+/// https://lichess.org/variant/threeCheck
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ThreeCheck;
+
+impl ThreeCheck {
+    pub const CHECKS_TO_WIN: u32 = 3;
+}
+
+impl Variant for ThreeCheck {
+    fn name(&self) -> &'static str {
+        "3check"
+    }
+
+    fn after_move(&self, previous: &ChessBoard, chess_board: &mut ChessBoard, _chess_move: ChessMove) {
+        if chess_board.inner.is_current_player_in_check() {
+            chess_board.inner.add_check(previous.inner.current_color);
+        }
+    }
+
+    fn outcome(&self, chess_board: &ChessBoard) -> Option<GameState> {
+        [PieceColor::White, PieceColor::Black].into_iter()
+            .find(|color| chess_board.inner.checks[*color as usize] >= ThreeCheck::CHECKS_TO_WIN)
+            .map(GameState::Win)
+    }
+
+    // Any piece can give check, even if it can't checkmate
+    fn has_mating_material(&self, chess_board: &ChessBoard, color: PieceColor) -> bool {
+        chess_board.inner.all_pieces[color as usize] & !chess_board.inner.pieces[PieceType::King as usize] != 0
+    }
+}
@@ -0,0 +1,200 @@
+// https://www.chessprogramming.org/Chess960_Perft_Results
+
+mod common;
+
+use puhl_chess::*;
+
+use common::mirror;
+
+mod tests {
+    use super::*;
+
+    fn test_position_helper(fen: &str, results: Vec<u64>) {
+        let chess_board = ChessBoard::new(Some(fen)).unwrap();
+        for (depth, result) in std::iter::zip(1.., results) {
+            let count = perft(&chess_board, depth);
+            assert_eq!(count, result);
+        }
+    }
+
+    #[test]
+    fn test_position_1() {
+        test_position_helper("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9", vec![ 21, 528, 12189, 326672 ]);
+    }
+
+    #[test]
+    fn test_position_2() {
+        test_position_helper("2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9", vec![ 21, 807, 18002, 667366 ]);
+    }
+
+    #[test]
+    fn test_position_3() {
+        test_position_helper("b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9", vec![ 20, 479, 10471, 273318 ]);
+    }
+
+    #[test]
+    fn test_position_4() {
+        test_position_helper("qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9", vec![ 22, 593, 13440, 382958 ]);
+    }
+
+    #[test]
+    fn test_position_5() {
+        test_position_helper("1nbbnrkr/p1p1ppp1/3p4/1p3P1p/3Pq2P/8/PPP1P1P1/QNBBNRKR w HFhf - 0 9", vec![ 28, 1120, 31058, 1171749 ]);
+    }
+
+    #[test]
+    fn test_position_6() {
+        test_position_helper("qnbnr1kr/ppp1b1pp/4p3/3p1p2/8/2NPP3/PPP1BPPP/QNB1R1KR w HEhe - 1 9", vec![ 29, 899, 26578, 824055 ]);
+    }
+
+    #[test]
+    fn test_position_7() {
+        test_position_helper("q1bnrkr1/ppppp2p/2n2p2/4b1p1/2NP4/8/PPP1PPPP/QNB1RRKB w ge - 1 9", vec![ 30, 860, 24566, 732757 ]);
+    }
+
+    #[test]
+    fn test_position_8() {
+        test_position_helper("qbn1brkr/ppp1p1p1/2n4p/3p1p2/P7/6PP/QPPPPP2/1BNNBRKR w HFhf - 0 9", vec![ 25, 635, 17054, 465806 ]);
+    }
+
+    #[test]
+    fn test_position_9() {
+        test_position_helper("qnnbbrkr/1p2ppp1/2pp3p/p7/1P5P/2NP4/P1P1PPP1/Q1NBBRKR w HFhf - 0 9", vec![ 24, 572, 15243, 384260 ]);
+    }
+
+    #[test]
+    fn test_position_10() {
+        test_position_helper("qn1rbbkr/ppp2p1p/1n1pp1p1/8/3P4/P6P/1PP1PPPK/QNNRBB1R w hd - 2 9", vec![ 28, 811, 23175, 679699 ]);
+    }
+
+    #[test]
+    fn test_start_positions() {
+        let chess_board = ChessBoard::from_chess960_index(518).unwrap();
+        let standard = ChessBoard::new(None).unwrap();
+        assert!(chess_board.chess960());
+        assert_eq!(chess_board.hash(), standard.hash());
+
+        let chess_board = ChessBoard::from_chess960_index(0).unwrap();
+        let expected = ChessBoard::new(Some("bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w HFhf - 0 1")).unwrap();
+        assert_eq!(chess_board.hash(), expected.hash());
+        assert_eq!(perft(&chess_board, 3), perft(&expected, 3));
+
+        assert!(ChessBoard::from_chess960_index(960).is_none());
+    }
+
+    #[test]
+    fn test_castling_notation() {
+        let mut chess_board = ChessBoard::new(Some("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1")).unwrap();
+        assert!(!chess_board.chess960());
+        assert!(chess_board.parse_move("e1g1").is_some());
+        assert!(chess_board.parse_move("e1c1").is_some());
+        assert!(chess_board.parse_move("e1h1").is_none());
+
+        chess_board.set_chess960(true);
+        assert!(chess_board.parse_move("e1g1").is_none());
+        let chess_move = chess_board.parse_move("e1h1").unwrap();
+        let (chess_board, move_type) = chess_board.make_move(chess_move).unwrap();
+        assert_eq!(move_type, MoveType::Castling);
+        let expected = ChessBoard::new(Some("r3k2r/8/8/8/8/8/8/R4RK1 b kq - 1 1")).unwrap();
+        assert_eq!(chess_board.hash(), expected.hash());
+    }
+
+    #[test]
+    fn test_castling_without_moving() {
+        // The king stays on g1 and only the rook moves
+        let chess_board = ChessBoard::new(Some("4k3/8/8/8/8/8/8/6KR w H - 0 1")).unwrap();
+        assert!(chess_board.chess960());
+        let (chess_board, move_type) = chess_board.make_move(chess_board.parse_move("g1h1").unwrap()).unwrap();
+        assert_eq!(move_type, MoveType::Castling);
+        assert_eq!(chess_board.hash(), ChessBoard::new(Some("4k3/8/8/8/8/8/8/5RK1 b - - 1 1")).unwrap().hash());
+
+        // The rook stays on f1 and only the king moves
+        let chess_board = ChessBoard::new(Some("4k3/8/8/8/8/8/8/4KR2 w F - 0 1")).unwrap();
+        let (chess_board, move_type) = chess_board.make_move(chess_board.parse_move("e1f1").unwrap()).unwrap();
+        assert_eq!(move_type, MoveType::Castling);
+        assert_eq!(chess_board.hash(), ChessBoard::new(Some("4k3/8/8/8/8/8/8/5RK1 b - - 1 1")).unwrap().hash());
+    }
+
+    #[test]
+    fn test_castling_through_attack() {
+        // The king may not pass the attacked f1, even though the rook ends there
+        let chess_board = ChessBoard::new(Some("4kr2/8/8/8/8/8/8/1R2K3 w B - 0 1")).unwrap();
+        assert!(chess_board.parse_move("e1b1").is_some());
+        let chess_board = ChessBoard::new(Some("5rk1/8/8/8/8/8/8/1K4R1 w G - 0 1")).unwrap();
+        assert!(chess_board.parse_move("b1g1").is_none());
+    }
+
+    #[test]
+    fn test_castling_fen() {
+        let shredder = ChessBoard::new(Some("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1")).unwrap();
+        let standard = ChessBoard::new(None).unwrap();
+        assert_eq!(shredder.hash(), standard.hash());
+
+        // An inner rook needs its file, KQkq always refers to the outermost rook
+        let inner = ChessBoard::new(Some("4k3/8/8/8/8/8/8/4K1RR w G - 0 1")).unwrap();
+        assert!(inner.parse_move("e1g1").is_some());
+        let outer = ChessBoard::new(Some("4k3/8/8/8/8/8/8/4K1RR w K - 0 1")).unwrap();
+        assert!(outer.parse_move("e1g1").is_none());
+        assert!(outer.parse_move("e1h1").is_none());
+
+        // A right without its rook is dropped, the other rights are kept
+        let no_rook = ChessBoard::new(Some("4k3/8/8/8/8/8/8/4K3 w K - 0 1")).unwrap();
+        assert_eq!(no_rook.hash(), ChessBoard::new(Some("4k3/8/8/8/8/8/8/4K3 w - - 0 1")).unwrap().hash());
+        let stale = ChessBoard::new(Some("r3k3/8/8/8/8/8/8/R3K3 w Kq - 0 1")).unwrap();
+        assert_eq!(stale.hash(), ChessBoard::new(Some("r3k3/8/8/8/8/8/8/R3K3 w q - 0 1")).unwrap().hash());
+    }
+
+    // ======= Double Fischer Random, both colors have their own back rank =======
+    // Mirroring the position vertically and swapping the colors doesn't change the number of moves
+    fn test_mirrored_helper(fen: &str, depth: usize) {
+        let chess_board = ChessBoard::new(Some(fen)).unwrap();
+        let mirrored = ChessBoard::new(Some(&mirror(fen))).unwrap();
+        assert_eq!(perft(&chess_board, depth), perft(&mirrored, depth));
+    }
+
+    #[test]
+    fn test_asymmetric_castling() {
+        // White castles with the g-rook on the king side, black with the b-rook on the queen side
+        let fen = "1r2k2r/8/8/8/8/8/8/R3K1R1 w Gb - 0 1";
+        let chess_board = ChessBoard::new(Some(fen)).unwrap();
+        assert_eq!(perft(&chess_board, 1), 25);
+        let mirrored = ChessBoard::new(Some(&mirror(fen))).unwrap();
+        assert_eq!(perft(&mirrored, 1), 25);
+
+        let (chess_board, move_type) = chess_board.make_move(chess_board.parse_move("e1g1").unwrap()).unwrap();
+        assert_eq!(move_type, MoveType::Castling);
+        // The rook on f1 takes f7 and f8 from the king
+        assert_eq!(perft(&chess_board, 1), 23);
+        let (chess_board, move_type) = chess_board.make_move(chess_board.parse_move("e8b8").unwrap()).unwrap();
+        assert_eq!(move_type, MoveType::Castling);
+        assert_eq!(chess_board.hash(), ChessBoard::new(Some("2kr3r/8/8/8/8/8/8/R4RK1 w - - 2 2")).unwrap().hash());
+    }
+
+    #[test]
+    fn test_asymmetric_castling_fen() {
+        // KQkq is decided by the rooks of each color
+        let standard = ChessBoard::new(Some("r3k1r1/8/8/8/8/8/8/1R2K2R w KQkq - 0 1")).unwrap();
+        let shredder = ChessBoard::new(Some("r3k1r1/8/8/8/8/8/8/1R2K2R w HBga - 0 1")).unwrap();
+        assert_eq!(standard.hash(), shredder.hash());
+
+        // Capturing the rook on b8 removes the right of black but not of white, whose rook is on the same file
+        let chess_board = ChessBoard::new(Some("1r2k3/8/8/8/8/8/8/1R2K3 w Bb - 0 1")).unwrap();
+        let (chess_board, _) = chess_board.make_move(chess_board.parse_move("b1b8").unwrap()).unwrap();
+        assert_eq!(chess_board.hash(), ChessBoard::new(Some("1R2k3/8/8/8/8/8/8/4K3 b - - 0 1")).unwrap().hash());
+        let chess_board = ChessBoard::new(Some("1r2k3/8/8/8/8/8/8/1R2K3 w Bb - 0 1")).unwrap();
+        let (chess_board, _) = chess_board.make_move(chess_board.parse_move("e1d1").unwrap()).unwrap();
+        let (chess_board, _) = chess_board.make_move(chess_board.parse_move("b8b1").unwrap()).unwrap();
+        assert_eq!(chess_board.hash(), ChessBoard::new(Some("4k3/8/8/8/8/8/8/1r1K4 w - - 0 2")).unwrap().hash());
+    }
+
+    #[test]
+    fn test_double_fischer_random_start() {
+        test_mirrored_helper("nrkbbqrn/pppppppp/8/8/8/8/PPPPPPPP/BBRKNNQR w HCgb - 0 1", 4);
+    }
+
+    #[test]
+    fn test_double_fischer_random_castling() {
+        test_mirrored_helper("1r1k2r1/pppppppp/8/8/8/8/PPPPPPPP/2R1K2R w HCgb - 0 1", 4);
+        test_mirrored_helper("r1k3r1/1pp2ppp/8/p2pp3/8/2N2N2/PPPPPPPP/1R2K1R1 b GBga - 0 1", 4);
+    }
+}
@@ -0,0 +1,151 @@
+use std::time::Duration;
+
+use puhl_chess::*;
+
+mod tests {
+    use super::*;
+
+    const fn seconds(seconds: u64) -> Duration {
+        Duration::from_secs(seconds)
+    }
+
+    fn clock(time_control: TimeControl) -> (Clock, FakeTimeSource) {
+        let time = FakeTimeSource::new();
+        (Clock::with_time_source(time_control, time.clone()), time)
+    }
+
+    #[test]
+    fn test_increment() {
+        let (mut clock, time) = clock(TimeControl::new(seconds(60), Delay::Increment(seconds(2))));
+        assert_eq!(clock.press(), None);
+
+        clock.start(PieceColor::White);
+        time.advance(seconds(5));
+        assert_eq!(clock.remaining(PieceColor::White), seconds(55));
+        assert_eq!(clock.press(), Some(seconds(57)));
+        assert_eq!(clock.running(), Some(PieceColor::Black));
+
+        time.advance(seconds(1));
+        assert_eq!(clock.press(), Some(seconds(61)));
+        assert_eq!(clock.remaining(PieceColor::White), seconds(57));
+    }
+
+    #[test]
+    fn test_bronstein_delay() {
+        let (mut clock, time) = clock(TimeControl::new(seconds(60), Delay::Bronstein(seconds(3))));
+        clock.start(PieceColor::White);
+        time.advance(seconds(2));
+        assert_eq!(clock.press(), Some(seconds(60)));
+
+        // Only the delay is given back when the move took longer
+        time.advance(seconds(10));
+        assert_eq!(clock.remaining(PieceColor::Black), seconds(50));
+        assert_eq!(clock.press(), Some(seconds(53)));
+    }
+
+    #[test]
+    fn test_simple_delay() {
+        let (mut clock, time) = clock(TimeControl::new(seconds(60), Delay::Simple(seconds(3))));
+        clock.start(PieceColor::White);
+        time.advance(seconds(2));
+        assert_eq!(clock.remaining(PieceColor::White), seconds(60));
+        assert_eq!(clock.press(), Some(seconds(60)));
+
+        time.advance(seconds(10));
+        assert_eq!(clock.remaining(PieceColor::Black), seconds(53));
+        assert_eq!(clock.press(), Some(seconds(53)));
+    }
+
+    #[test]
+    fn test_periods() {
+        // 2/90+30, then 30+30, with the moves shortened to two
+        let increment = Delay::Increment(seconds(30));
+        let (mut clock, time) = clock(TimeControl { periods: vec![
+            TimePeriod { moves: Some(2), time: seconds(90 * 60), delay: increment },
+            TimePeriod { moves: None, time: seconds(30 * 60), delay: increment },
+        ] });
+
+        clock.start(PieceColor::White);
+        assert_eq!(clock.moves_to_go(PieceColor::White), Some(2));
+        time.advance(seconds(60));
+        assert_eq!(clock.press(), Some(seconds(89 * 60 + 30)));
+        assert_eq!(clock.moves_to_go(PieceColor::White), Some(1));
+        clock.press();
+        time.advance(seconds(60));
+        assert_eq!(clock.press(), Some(seconds(90 * 60 - 2 * 60 + 2 * 30 + 30 * 60)));
+        assert_eq!(clock.moves_to_go(PieceColor::White), None);
+        assert_eq!(clock.moves_to_go(PieceColor::Black), Some(1));
+    }
+
+    #[test]
+    fn test_repeating_period() {
+        let (mut clock, _) = clock(TimeControl { periods: vec![ TimePeriod { moves: Some(1), time: seconds(10), delay: Delay::None } ] });
+        clock.start(PieceColor::White);
+        assert_eq!(clock.press(), Some(seconds(20)));
+        clock.press();
+        assert_eq!(clock.press(), Some(seconds(30)));
+        assert_eq!(clock.moves_to_go(PieceColor::White), Some(1));
+    }
+
+    #[test]
+    fn test_flag_fall() {
+        let (mut clock, time) = clock(TimeControl::new(seconds(10), Delay::Increment(seconds(5))));
+        clock.start(PieceColor::White);
+        time.advance(seconds(9));
+        assert_eq!(clock.flagged(), None);
+        time.advance(seconds(1));
+        assert_eq!(clock.flagged(), Some(PieceColor::White));
+
+        // The increment is not added once the time has run out
+        assert_eq!(clock.press(), None);
+        assert_eq!(clock.running(), None);
+        assert_eq!(clock.remaining(PieceColor::White), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_stop() {
+        let (mut clock, time) = clock(TimeControl::new(seconds(10), Delay::Increment(seconds(5))));
+        clock.start(PieceColor::Black);
+        time.advance(seconds(4));
+        clock.stop();
+        time.advance(seconds(4));
+        assert_eq!(clock.running(), None);
+        assert_eq!(clock.remaining(PieceColor::Black), seconds(6));
+        assert_eq!(clock.remaining(PieceColor::White), seconds(10));
+    }
+
+    #[test]
+    fn test_game_clock() {
+        let time = FakeTimeSource::new();
+        let mut clock = Clock::with_time_source(TimeControl::new(seconds(60), Delay::Increment(seconds(1))), time.clone());
+        clock.start(PieceColor::White);
+        let mut game = Game::new(None).unwrap();
+        game.set_clock(clock);
+
+        time.advance(seconds(10));
+        assert!(game.make_move(game.chess_board().parse_move("e2e4").unwrap()).is_some());
+        assert_eq!(game.moves()[0].clock, Some(seconds(51)));
+        assert_eq!(game.clock().unwrap().running(), Some(PieceColor::Black));
+
+        time.advance(seconds(60));
+        assert!(game.make_move(game.chess_board().parse_move("e7e5").unwrap()).is_none());
+        assert_eq!(game.result().state, GameState::Win(PieceColor::White));
+        assert_eq!(game.result().termination, Some(Termination::Timeout));
+        assert_eq!(game.clock().unwrap().running(), None);
+    }
+
+    #[test]
+    fn test_flag_against_lone_king() {
+        let time = FakeTimeSource::new();
+        let mut clock = Clock::with_time_source(TimeControl::new(seconds(60), Delay::None), time.clone());
+        clock.start(PieceColor::White);
+        let mut game = Game::new(Some("7k/8/8/8/8/8/8/KQ6 w - - 0 1")).unwrap();
+        game.set_clock(clock);
+
+        time.advance(seconds(60));
+        assert!(game.check_flag());
+        assert_eq!(game.result().state, GameState::Draw);
+        assert_eq!(game.result().termination, Some(Termination::TimeoutVsInsufficientMaterial));
+        assert!(!game.check_flag());
+    }
+}
@@ -0,0 +1,59 @@
+// Helpers shared by the integration tests, every test crate uses only some of them
+#![allow(dead_code)]
+
+use std::io::{ BufRead, BufReader, Lines, Write };
+use std::process::{ Child, ChildStdin, ChildStdout, Command, Stdio };
+
+// The harness the protocol tests use to talk to an engine binary over stdin and stdout
+pub struct Engine {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: Lines<BufReader<ChildStdout>>,
+}
+
+impl Engine {
+    // Starts the binary at "path", for example env!("CARGO_BIN_EXE_puhl-uci")
+    pub fn new(path: &str) -> Self {
+        let mut child = Command::new(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap()).lines();
+        Engine { child, stdin, stdout }
+    }
+
+    pub fn send(&mut self, command: &str) {
+        writeln!(self.stdin, "{}", command).unwrap();
+    }
+
+    // Returns the lines up to and including the first one which starts with "prefix"
+    pub fn read_until(&mut self, prefix: &str) -> Vec<String> {
+        let mut lines = vec![];
+        for line in &mut self.stdout {
+            let line = line.unwrap();
+            let done = line.starts_with(prefix);
+            lines.push(line);
+            if done {
+                return lines;
+            }
+        }
+        panic!("The engine quit before \"{}\": {:?}", prefix, lines);
+    }
+
+    pub fn quit(mut self) {
+        self.send("quit");
+        assert!(self.child.wait().unwrap().success());
+    }
+}
+
+// Mirrors the position vertically and swaps the colors, including the side to move
+pub fn mirror(fen: &str) -> String {
+    let fields: Vec<&str> = fen.split_whitespace().collect();
+    let swap_case = |field: &str| field.chars().map(|c| if c.is_ascii_uppercase() { c.to_ascii_lowercase() } else { c.to_ascii_uppercase() }).collect::<String>();
+    let placement = fields[0].split('/').rev().map(swap_case).collect::<Vec<_>>().join("/");
+    let turn = if fields[1] == "w" { "b" } else { "w" };
+    let en_passant = fields[3].chars().map(|c| match c { '3' => '6', '6' => '3', c => c }).collect::<String>();
+    format!("{} {} {} {} {} {}", placement, turn, swap_case(fields[2]), en_passant, fields[4], fields[5])
+}
@@ -0,0 +1,75 @@
+mod common;
+
+use puhl_chess::*;
+
+use common::mirror;
+
+mod tests {
+    use super::*;
+
+    const POSITIONS: [&str; 5] = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    ];
+
+
+    #[test]
+    fn test_start_position() {
+        let chess_board = ChessBoard::new(None).unwrap();
+        assert_eq!(Evaluation::new(&chess_board).score(PieceColor::White), 0);
+        assert_eq!(evaluate(&chess_board), TEMPO);
+    }
+
+    #[test]
+    fn test_symmetry() {
+        for fen in POSITIONS {
+            let chess_board = ChessBoard::new(Some(fen)).unwrap();
+            let flipped = ChessBoard::new(Some(&mirror(fen))).unwrap();
+            assert_eq!(evaluate(&flipped), evaluate(&chess_board));
+            assert_eq!(Evaluation::new(&flipped).score(PieceColor::White), -Evaluation::new(&chess_board).score(PieceColor::White));
+        }
+    }
+
+    #[test]
+    fn test_material() {
+        // White is a queen up, black is to move
+        let chess_board = ChessBoard::new(Some("rnb1kbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1")).unwrap();
+        assert!(evaluate(&chess_board) < -800);
+        assert!(Evaluation::new(&chess_board).score(PieceColor::White) > 800);
+    }
+
+    #[test]
+    fn test_tapered() {
+        // A king in the center is bad in the middlegame and good in the endgame
+        let endgame = ChessBoard::new(Some("4k3/8/8/8/3K4/8/8/8 w - - 0 1")).unwrap();
+        let corner = ChessBoard::new(Some("4k3/8/8/8/8/8/8/K7 w - - 0 1")).unwrap();
+        assert!(evaluate(&endgame) > evaluate(&corner));
+    }
+
+    #[test]
+    fn test_incremental_update() {
+        // Every move two plies deep, including castling, en passant and promotions, is updated the same as it is evaluated from scratch
+        for fen in POSITIONS {
+            let chess_board = ChessBoard::new(Some(fen)).unwrap();
+            let evaluation = Evaluation::new(&chess_board);
+            for chess_move in chess_board.legal_moves() {
+                let (next, _) = chess_board.make_move(chess_move).unwrap();
+                let mut next_evaluation = evaluation;
+                next_evaluation.update(&chess_board, &next);
+                assert_eq!(next_evaluation, Evaluation::new(&next));
+                for reply in next.legal_moves() {
+                    let (after_reply, _) = next.make_move(reply).unwrap();
+                    let mut reply_evaluation = next_evaluation;
+                    reply_evaluation.update(&next, &after_reply);
+                    assert_eq!(reply_evaluation, Evaluation::new(&after_reply));
+                    // Unmaking the reply restores the evaluation
+                    reply_evaluation.update(&after_reply, &next);
+                    assert_eq!(reply_evaluation, next_evaluation);
+                }
+            }
+        }
+    }
+}
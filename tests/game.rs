@@ -0,0 +1,271 @@
+use puhl_chess::*;
+
+mod tests {
+    use super::*;
+
+    fn play(game: &mut Game, moves: &[&str]) {
+        for uci in moves {
+            let chess_move = game.chess_board().parse_move(uci).unwrap();
+            assert!(game.make_move(chess_move).is_some(), "{}", uci);
+        }
+    }
+
+    #[test]
+    fn test_history() {
+        let mut game = Game::new(None).unwrap();
+        play(&mut game, &[ "e2e4", "d7d5", "e4d5", "d8d5" ]);
+        assert_eq!(game.ply(), 4);
+        assert_eq!(game.moves()[2].move_type, MoveType::Normal(Some(PieceType::Pawn)));
+        assert_eq!(game.moves()[2].captured, Some(PieceType::Pawn));
+        assert_eq!(game.moves()[3].captured, Some(PieceType::Pawn));
+        assert_eq!(game.moves()[0].captured, None);
+        assert_eq!(game.chess_board().inner.full_moves, 3);
+        assert_eq!(game.half_moves(), 0);
+        assert_eq!(game.start_position().hash(), ChessBoard::new(None).unwrap().hash());
+    }
+
+    #[test]
+    fn test_en_passant_capture() {
+        let mut game = Game::new(Some("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1")).unwrap();
+        play(&mut game, &[ "d4e3" ]);
+        assert_eq!(game.moves()[0].move_type, MoveType::EnPassant);
+        assert_eq!(game.moves()[0].captured, Some(PieceType::Pawn));
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut game = Game::new(None).unwrap();
+        let start = game.chess_board().hash();
+        assert!(!game.undo());
+        assert!(!game.redo());
+
+        play(&mut game, &[ "e2e4", "e7e5", "g1f3" ]);
+        let after_nf3 = game.chess_board().hash();
+        assert!(game.undo());
+        assert!(game.undo());
+        assert_eq!(game.ply(), 1);
+        assert!(game.redo());
+        assert!(game.redo());
+        assert!(!game.redo());
+        assert_eq!(game.chess_board().hash(), after_nf3);
+
+        assert!(game.go_to(0));
+        assert_eq!(game.chess_board().hash(), start);
+        assert!(game.go_to(3));
+        assert!(!game.go_to(4));
+        assert_eq!(game.moves().len(), 3);
+    }
+
+    #[test]
+    fn test_new_move_truncates_future() {
+        let mut game = Game::new(None).unwrap();
+        play(&mut game, &[ "e2e4", "e7e5", "g1f3" ]);
+        assert!(game.go_to(1));
+        play(&mut game, &[ "c7c5" ]);
+        assert_eq!(game.moves().len(), 2);
+        assert_eq!(game.moves()[1].chess_move.to_string(), "c7c5");
+        assert!(!game.redo());
+    }
+
+    #[test]
+    fn test_illegal_move() {
+        let mut game = Game::new(None).unwrap();
+        let chess_move = ChessMove { src: Index::new(11).unwrap(), dst: Index::new(35).unwrap(), promotion: None, drop: None };
+        assert!(game.make_move(chess_move).is_none());
+        assert_eq!(game.ply(), 0);
+    }
+
+    #[test]
+    fn test_checkmate() {
+        let mut game = Game::new(None).unwrap();
+        assert_eq!(game.result().to_string(), "*");
+        play(&mut game, &[ "f2f3", "e7e5", "g2g4", "d8h4" ]);
+        let result = game.result();
+        assert_eq!(result.state, GameState::Win(PieceColor::Black));
+        assert_eq!(result.termination, Some(Termination::Checkmate));
+        assert_eq!(result.to_string(), "0-1");
+
+        // No moves can be made after the game has ended, but it can still be navigated
+        let chess_move = ChessMove { src: Index::new(8).unwrap(), dst: Index::new(16).unwrap(), promotion: None, drop: None };
+        assert!(game.make_move(chess_move).is_none());
+        assert!(game.undo());
+        assert_eq!(game.result().termination, None);
+    }
+
+    #[test]
+    fn test_stalemate() {
+        let mut game = Game::new(Some("7k/8/6Q1/8/8/8/8/K7 w - - 0 1")).unwrap();
+        play(&mut game, &[ "g6f7" ]);
+        assert_eq!(game.result().termination, Some(Termination::Stalemate));
+        assert_eq!(game.result().to_string(), "1/2-1/2");
+    }
+
+    #[test]
+    fn test_repetition() {
+        let mut game = Game::new(None).unwrap();
+        assert_eq!(game.repetition_count(), 1);
+        for repetition in 2..=5 {
+            play(&mut game, &[ "g1f3", "g8f6", "f3g1", "f6g8" ]);
+            assert_eq!(game.repetition_count(), repetition);
+        }
+        assert_eq!(game.result().termination, Some(Termination::FivefoldRepetition));
+        assert_eq!(game.result().state, GameState::Draw);
+    }
+
+    #[test]
+    fn test_repetition_ignores_uncapturable_en_passant() {
+        // The position after 1. e4 has an en passant square, but no pawn can capture on it
+        let mut game = Game::new(None).unwrap();
+        play(&mut game, &[ "e2e4", "g8f6", "g1f3", "f6g8", "f3g1" ]);
+        assert_eq!(game.repetition_count(), 2);
+    }
+
+    #[test]
+    fn test_seventy_five_move_rule() {
+        let mut game = Game::new(Some("7k/8/8/8/8/8/8/K6R w - - 148 100")).unwrap();
+        play(&mut game, &[ "h1h2" ]);
+        assert_eq!(game.half_moves(), 149);
+        assert_eq!(game.result().termination, None);
+        play(&mut game, &[ "h8g8" ]);
+        assert_eq!(game.result().termination, Some(Termination::SeventyFiveMoveRule));
+
+        // A pawn move or capture resets the count
+        let mut game = Game::new(Some("7k/8/8/8/8/8/P7/K6R w - - 148 100")).unwrap();
+        play(&mut game, &[ "a2a3" ]);
+        assert_eq!(game.half_moves(), 0);
+    }
+
+    #[test]
+    fn test_resignation() {
+        let mut game = Game::new(None).unwrap();
+        play(&mut game, &[ "e2e4" ]);
+        assert!(game.resign(PieceColor::Black));
+        let result = game.result();
+        assert_eq!(result.state, GameState::Win(PieceColor::White));
+        assert_eq!(result.termination, Some(Termination::Resignation));
+        assert_eq!(result.to_string(), "1-0");
+
+        // The game has ended, so it can't be ended again or continued
+        assert!(!game.resign(PieceColor::White));
+        assert!(game.make_move(game.chess_board().parse_move("e7e5").unwrap()).is_none());
+
+        // The ending applies from the ply it was given at, but a new move from an earlier position discards it
+        assert!(game.undo());
+        assert_eq!(game.result().termination, None);
+        assert!(game.redo());
+        assert_eq!(game.result().termination, Some(Termination::Resignation));
+        assert!(game.undo());
+        play(&mut game, &[ "d2d4" ]);
+        assert_eq!(game.result().termination, None);
+    }
+
+    #[test]
+    fn test_timeout_abandonment_adjudication() {
+        let mut game = Game::new(None).unwrap();
+        assert!(game.time_out(PieceColor::White));
+        assert_eq!(game.result().state, GameState::Win(PieceColor::Black));
+        assert_eq!(game.result().termination, Some(Termination::Timeout));
+
+        let mut game = Game::new(None).unwrap();
+        assert!(game.abandon(PieceColor::Black));
+        assert_eq!(game.result().state, GameState::Win(PieceColor::White));
+        assert_eq!(game.result().termination, Some(Termination::Abandonment));
+
+        let mut game = Game::new(None).unwrap();
+        assert!(!game.adjudicate(GameState::Playing));
+        assert!(game.adjudicate(GameState::Draw));
+        assert_eq!(game.result().to_string(), "1/2-1/2");
+        assert_eq!(game.result().termination, Some(Termination::Adjudication));
+    }
+
+    #[test]
+    fn test_draw_offer() {
+        let mut game = Game::new(None).unwrap();
+        assert!(!game.accept_draw(PieceColor::Black));
+
+        // White offers a draw together with its move, the offer stands until black has moved
+        assert!(game.offer_draw(PieceColor::White));
+        play(&mut game, &[ "e2e4" ]);
+        assert_eq!(game.draw_offer(), Some(PieceColor::White));
+        assert!(!game.offer_draw(PieceColor::Black));
+        assert!(!game.accept_draw(PieceColor::White));
+        play(&mut game, &[ "e7e5" ]);
+        assert_eq!(game.draw_offer(), None);
+
+        // Declined offers are withdrawn
+        assert!(game.offer_draw(PieceColor::White));
+        assert!(game.decline_draw(PieceColor::Black));
+        assert_eq!(game.draw_offer(), None);
+        assert!(!game.decline_draw(PieceColor::Black));
+
+        assert!(game.offer_draw(PieceColor::White));
+        play(&mut game, &[ "g1f3" ]);
+        assert!(game.accept_draw(PieceColor::Black));
+        assert_eq!(game.result().state, GameState::Draw);
+        assert_eq!(game.result().termination, Some(Termination::DrawAgreement));
+        assert!(!game.offer_draw(PieceColor::White));
+    }
+
+    #[test]
+    fn test_claim_threefold_repetition() {
+        let mut game = Game::new(None).unwrap();
+        play(&mut game, &[ "g1f3", "g8f6", "f3g1", "f6g8" ]);
+        assert_eq!(game.claimable_draw(), None);
+        assert!(!game.claim_draw());
+        play(&mut game, &[ "g1f3", "g8f6", "f3g1", "f6g8" ]);
+        assert_eq!(game.claimable_draw(), Some(Termination::ThreefoldRepetition));
+        assert!(game.claim_draw());
+        assert_eq!(game.result().state, GameState::Draw);
+        assert_eq!(game.result().termination, Some(Termination::ThreefoldRepetition));
+    }
+
+    #[test]
+    fn test_claim_fifty_move_rule() {
+        let mut game = Game::new(Some("7k/8/8/8/8/8/8/K6R w - - 98 80")).unwrap();
+        play(&mut game, &[ "h1h2" ]);
+        assert_eq!(game.claimable_draw(), None);
+        play(&mut game, &[ "h8g8" ]);
+        assert_eq!(game.claimable_draw(), Some(Termination::FiftyMoveRule));
+        assert!(game.claim_draw());
+        assert_eq!(game.result().termination, Some(Termination::FiftyMoveRule));
+    }
+
+    #[test]
+    fn test_mating_material() {
+        let has_mating_material = |fen: &str, color: PieceColor| ChessBoard::new(Some(fen)).unwrap().has_mating_material(color);
+        assert!(has_mating_material("7k/8/8/8/8/8/8/KQ6 w - - 0 1", PieceColor::White));
+        assert!(!has_mating_material("7k/8/8/8/8/8/8/KQ6 w - - 0 1", PieceColor::Black));
+        assert!(has_mating_material("7k/8/8/8/8/8/8/KP6 w - - 0 1", PieceColor::White));
+        assert!(!has_mating_material("7k/8/8/8/8/8/8/KN6 w - - 0 1", PieceColor::White));
+        assert!(has_mating_material("7k/7p/8/8/8/8/8/KN6 w - - 0 1", PieceColor::White));
+        assert!(has_mating_material("7k/8/8/8/8/8/8/KNN5 w - - 0 1", PieceColor::White));
+        assert!(!has_mating_material("7k/8/8/8/8/8/8/KB6 w - - 0 1", PieceColor::White));
+        assert!(!has_mating_material("7k/8/8/8/8/8/6b1/KB6 w - - 0 1", PieceColor::White));
+        assert!(has_mating_material("7k/8/8/8/8/8/5b2/KB6 w - - 0 1", PieceColor::White));
+        assert!(has_mating_material("7k/8/8/8/8/8/8/KBB5 w - - 0 1", PieceColor::White));
+    }
+
+    #[test]
+    fn test_insufficient_material() {
+        let mut game = Game::new(Some("7k/8/8/8/8/8/8/Kr4N1 w - - 0 1")).unwrap();
+        play(&mut game, &[ "a1a2", "b1c1" ]);
+        assert_eq!(game.result().termination, None);
+
+        let mut game = Game::new(Some("7k/8/8/8/8/8/8/Kr4N1 w - - 0 1")).unwrap();
+        play(&mut game, &[ "a1b1" ]);
+        assert_eq!(game.result().state, GameState::Draw);
+        assert_eq!(game.result().termination, Some(Termination::InsufficientMaterial));
+    }
+
+    #[test]
+    fn test_time_out() {
+        let mut game = Game::new(Some("7k/8/8/8/8/8/8/KQ6 w - - 0 1")).unwrap();
+        assert!(game.time_out(PieceColor::Black));
+        assert_eq!(game.result().state, GameState::Win(PieceColor::White));
+
+        let mut game = Game::new(Some("7k/8/8/8/8/8/8/KQ6 w - - 0 1")).unwrap();
+        assert!(game.time_out(PieceColor::White));
+        assert_eq!(game.result().state, GameState::Draw);
+        assert_eq!(game.result().termination, Some(Termination::TimeoutVsInsufficientMaterial));
+    }
+}
@@ -0,0 +1,127 @@
+use puhl_chess::*;
+
+mod tests {
+    use super::*;
+
+    const POSITION: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+    fn parse(chess_board: &ChessBoard, uci: &str) -> ChessMove {
+        chess_board.parse_move(uci).unwrap()
+    }
+
+    #[test]
+    fn test_every_move_once() {
+        let positions = [
+            ChessBoard::INITIAL_POSITION_FEN,
+            POSITION,
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        ];
+        let history = HistoryTable::new();
+        for fen in positions {
+            let chess_board = ChessBoard::new(Some(fen)).unwrap();
+            let mut legal_moves = chess_board.legal_moves();
+            let mut killers = Killers::new();
+            killers.add(legal_moves[0]);
+            killers.add(legal_moves[legal_moves.len() - 1]);
+
+            let mut picked: Vec<ChessMove> = MovePicker::new(&chess_board, Some(legal_moves[1]), killers, Some(legal_moves[0]), &history).collect();
+            legal_moves.sort_by_key(|chess_move| chess_move.to_string());
+            picked.sort_by_key(|chess_move| chess_move.to_string());
+            assert_eq!(picked, legal_moves);
+        }
+    }
+
+    #[test]
+    fn test_order() {
+        let chess_board = ChessBoard::new(Some(POSITION)).unwrap();
+        let tt_move = parse(&chess_board, "e1g1");
+        let mut killers = Killers::new();
+        killers.add(parse(&chess_board, "a2a3"));
+        killers.add(parse(&chess_board, "a1b1"));
+        let countermove = parse(&chess_board, "d5d6");
+        let mut history = HistoryTable::new();
+        history.update(PieceColor::White, parse(&chess_board, "g2g3"), 100);
+        history.update(PieceColor::White, parse(&chess_board, "e5d3"), 50);
+
+        let moves: Vec<String> = MovePicker::new(&chess_board, Some(tt_move), killers, Some(countermove), &history)
+            .map(|chess_move| chess_move.to_string())
+            .collect();
+        assert_eq!(moves.len(), 48);
+        assert_eq!(moves[0], "e1g1");
+        assert_eq!(moves[1], "e2a6");
+        assert_eq!(moves[4..7], ["a1b1", "a2a3", "d5d6"]);
+        assert_eq!(moves[7..9], ["g2g3", "e5d3"]);
+        // The captures which lose material come last
+        assert_eq!(moves[43], "f3f6");
+        assert_eq!(moves[47], "f3h3");
+    }
+
+    #[test]
+    fn test_mvv_lva() {
+        let chess_board = ChessBoard::new(Some(POSITION)).unwrap();
+        let moves: Vec<String> = MovePicker::captures(&chess_board, None).map(|chess_move| chess_move.to_string()).collect();
+        // The captures which don't lose material come first, the bishop takes the bishop before the pawns take pawns
+        assert_eq!(moves.len(), 8);
+        assert_eq!(moves[0], "e2a6");
+        let mut pawn_captures = moves[1..3].to_vec();
+        pawn_captures.sort();
+        assert_eq!(pawn_captures, ["d5e6", "g2h3"]);
+        // The queen takes the defended knight before the knight takes defended pawns, and the queen takes the pawn the rook defends last
+        assert_eq!(moves[3], "f3f6");
+        let mut knight_captures = moves[4..7].to_vec();
+        knight_captures.sort();
+        assert_eq!(knight_captures, ["e5d7", "e5f7", "e5g6"]);
+        assert_eq!(moves[7], "f3h3");
+    }
+
+    #[test]
+    fn test_captures() {
+        let chess_board = ChessBoard::new(Some("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8")).unwrap();
+        let moves: Vec<String> = MovePicker::captures(&chess_board, None).map(|chess_move| chess_move.to_string()).collect();
+        // The promotion to a queen which captures the bishop comes first, the king takes the knight before the bishop takes the pawn
+        assert_eq!(moves.len(), 6);
+        assert_eq!(moves[0], "d7c8q");
+        assert_eq!(moves[4..], ["e1f2", "c4f7"]);
+    }
+
+    #[test]
+    fn test_illegal_moves_ignored() {
+        let chess_board = ChessBoard::new(None).unwrap();
+        let illegal = ChessMove::new(Index::from_algebraic("e2").unwrap(), Index::from_algebraic("e5").unwrap(), None);
+        let mut killers = Killers::new();
+        killers.add(illegal);
+        let moves: Vec<ChessMove> = MovePicker::new(&chess_board, Some(illegal), killers, Some(illegal), &HistoryTable::new()).collect();
+        assert_eq!(moves.len(), 20);
+        assert!(!moves.contains(&illegal));
+    }
+
+    #[test]
+    fn test_heuristic_tables() {
+        let chess_board = ChessBoard::new(None).unwrap();
+        let e4 = parse(&chess_board, "e2e4");
+        let d4 = parse(&chess_board, "d2d4");
+
+        let mut killers = Killers::new();
+        killers.add(e4);
+        killers.add(e4);
+        assert_eq!(killers.moves(), [Some(e4), None]);
+        killers.add(d4);
+        assert_eq!(killers.moves(), [Some(d4), Some(e4)]);
+
+        let mut history = HistoryTable::new();
+        for _ in 0..1000 {
+            history.update(PieceColor::White, e4, 4000);
+        }
+        assert!(history.get(PieceColor::White, e4) <= MAX_HISTORY);
+        assert_eq!(history.get(PieceColor::Black, e4), 0);
+        history.clear();
+        assert_eq!(history.get(PieceColor::White, e4), 0);
+
+        let mut countermoves = CountermoveTable::new();
+        countermoves.set(e4, d4);
+        assert_eq!(countermoves.get(e4), Some(d4));
+        assert_eq!(countermoves.get(d4), None);
+    }
+}
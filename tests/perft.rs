@@ -3,89 +3,160 @@ use puhl_chess::*;
 
 mod tests {
     use super::*;
-    
-    fn count_moves(chess_board: &ChessBoard, depth: usize) -> u64 {
-        if depth == 0 {
-            return 1;
-        }
-        let mut count: u64 = 0;
-        for rank in 0..BOARD_RANKS {
-            for file in 0..BOARD_FILES {
-                let rank = Rank::new(rank).expect("Rank out of bounds");
-                let file = File::new(file).expect("File out of bounds");
-                let moves = chess_board.square(rank, file).get_moves();
-                let moves = match moves {
-                    None => continue,
-                    Some(m) => m,
-                };
-                for chess_move in moves {
-                    let (chess_board, _)= chess_move.make_move();
-                    match chess_board {
-                        MoveResult::ChessBoard(chess_board) => { 
-                            count += count_moves(&chess_board, depth - 1);
-                        }
-                        MoveResult::PawnPromotionResolver(pawn_promotion_resolver) => {
-                            for promotion_piece in [ PieceType::Knight, PieceType::Bishop, PieceType::Rook, PieceType::Queen ] {
-                                let (chess_board, _)= match promotion_piece {
-                                    PieceType::Knight => pawn_promotion_resolver.resolve_knight(),
-                                    PieceType::Bishop => pawn_promotion_resolver.resolve_bishop(),
-                                    PieceType::Rook => pawn_promotion_resolver.resolve_rook(),
-                                    PieceType::Queen => pawn_promotion_resolver.resolve_queen(),
-                                    _ => unreachable!()
-                                };
-                                count += count_moves(&chess_board, depth - 1);
-                            }
-                        }
-                    }
-                }
-            }
-        }
-        return count;
-    }
 
     // ======= https://www.chessprogramming.org/Perft_Results =======
-    fn test_position_helper(fen: &str, max_depth: usize, results: Vec<u64>) {
+    fn test_position_helper(fen: &str, results: Vec<u64>) {
         let chess_board = ChessBoard::new(Some(fen)).unwrap();
-        for (depth , result) in std::iter::zip(1..max_depth, results ){
-            let count = count_moves(&chess_board, depth);
+        for (depth, result) in std::iter::zip(1.., results) {
+            let count = perft(&chess_board, depth);
             assert_eq!(count, result);
         }
     }
 
     #[test]
     fn test_inital_position() {
-        test_position_helper("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", 4, vec![ 20, 400, 8902, 197281 ]);
+        test_position_helper("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", vec![ 20, 400, 8902, 197281 ]);
     }
 
     #[test]
     fn test_position_2() {
-        test_position_helper("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", 3, vec![ 48, 2039, 97862 ]);
+        test_position_helper("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", vec![ 48, 2039, 97862 ]);
     }
 
     #[test]
     fn test_position_3() {
-        test_position_helper("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 4, vec![ 14, 191, 2812, 43238 ]);
+        test_position_helper("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", vec![ 14, 191, 2812, 43238, 674624 ]);
     }
 
     #[test]
     fn test_position_4() {
-        test_position_helper("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1", 3, vec![ 6, 264, 9467 ]);
+        test_position_helper("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1", vec![ 6, 264, 9467, 422333 ]);
     }
 
     #[test]
     fn test_position_5() {
-        test_position_helper("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8", 3, vec![ 44, 1486, 62379 ]);
+        test_position_helper("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8", vec![ 44, 1486, 62379 ]);
     }
 
     #[test]
     fn test_position_6() {
-        test_position_helper("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10", 3, vec![ 46, 2079, 89890 ]);
+        test_position_helper("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10", vec![ 46, 2079, 89890 ]);
     }
 
     #[test]
     fn test_pawn_promotion() {
-        let mut chess_board = ChessBoard::new(Some("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1")).unwrap();
-        let count = count_moves(&mut chess_board, 4);
+        let chess_board = ChessBoard::new(Some("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1")).unwrap();
+        let count = perft(&chess_board, 4);
         assert_eq!(count, 422333);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_divide() {
+        // https://www.chessprogramming.org/Perft_Results#Position_2
+        let chess_board = ChessBoard::new(Some("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")).unwrap();
+        let divide = perft_divide(&chess_board, 2);
+        assert_eq!(divide.len(), 48);
+        assert_eq!(divide.iter().map(|(_, count)| count).sum::<u64>(), 2039);
+
+        let castling = divide.iter().find(|(chess_move, _)| chess_move.to_string() == "e1g1").unwrap();
+        assert_eq!(castling.1, 43);
+        let (after_castling, _) = chess_board.make_move(castling.0).unwrap();
+        assert_eq!(perft(&after_castling, 1), 43);
+
+        assert!(perft_divide(&chess_board, 0).is_empty());
+        assert_eq!(perft(&chess_board, 0), 1);
+    }
+
+    #[test]
+    fn test_divide_promotions() {
+        let chess_board = ChessBoard::new(Some("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 b kq - 0 1")).unwrap();
+        let promotions: Vec<String> = perft_divide(&chess_board, 1).iter()
+            .map(|(chess_move, _)| chess_move.to_string())
+            .filter(|chess_move| chess_move.starts_with("b2a1"))
+            .collect();
+        assert_eq!(promotions.len(), 4);
+        for promotion in [ "b2a1n", "b2a1b", "b2a1r", "b2a1q" ] {
+            assert!(promotions.iter().any(|chess_move| chess_move == promotion));
+        }
+    }
+
+    #[test]
+    fn test_hash_and_threads() {
+        let chess_board = ChessBoard::new(Some("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")).unwrap();
+        let options = PerftOptions { hash_size_mb: 1, threads: 4 };
+        assert_eq!(perft_with_options(&chess_board, 3, &options), 97862);
+        assert_eq!(perft_divide_with_options(&chess_board, 3, &options), perft_divide(&chess_board, 3));
+    }
+
+    // Columns: nodes, captures, en passants, castles, promotions, checks, discovered checks, double checks, checkmates
+    fn test_stats_helper(fen: &str, results: Vec<[u64; 9]>) {
+        let chess_board = ChessBoard::new(Some(fen)).unwrap();
+        for (depth, result) in std::iter::zip(1.., results) {
+            let stats = perft_stats(&chess_board, depth);
+            let columns = [ stats.nodes, stats.captures, stats.en_passants, stats.castles, stats.promotions,
+                            stats.checks, stats.discovered_checks, stats.double_checks, stats.checkmates ];
+            assert_eq!(columns, result, "depth {}", depth);
+            assert_eq!(stats.nodes, perft(&chess_board, depth));
+        }
+    }
+
+    #[test]
+    fn test_stats_inital_position() {
+        test_stats_helper("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", vec![
+            [ 20, 0, 0, 0, 0, 0, 0, 0, 0 ],
+            [ 400, 0, 0, 0, 0, 0, 0, 0, 0 ],
+            [ 8902, 34, 0, 0, 0, 12, 0, 0, 0 ],
+            [ 197281, 1576, 0, 0, 0, 469, 0, 0, 8 ],
+            [ 4865609, 82719, 258, 0, 0, 27351, 6, 0, 347 ],
+        ]);
+    }
+
+    #[test]
+    fn test_stats_position_2() {
+        test_stats_helper("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", vec![
+            [ 48, 8, 0, 2, 0, 0, 0, 0, 0 ],
+            [ 2039, 351, 1, 91, 0, 3, 0, 0, 0 ],
+            [ 97862, 17102, 45, 3162, 0, 993, 0, 0, 1 ],
+            [ 4085603, 757163, 1929, 128013, 15172, 25523, 42, 6, 43 ],
+        ]);
+    }
+
+    #[test]
+    fn test_stats_position_3() {
+        test_stats_helper("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", vec![
+            [ 14, 1, 0, 0, 0, 2, 0, 0, 0 ],
+            [ 191, 14, 0, 0, 0, 10, 0, 0, 0 ],
+            [ 2812, 209, 2, 0, 0, 267, 3, 0, 0 ],
+            [ 43238, 3348, 123, 0, 0, 1680, 106, 0, 17 ],
+            [ 674624, 52051, 1165, 0, 0, 52950, 1292, 3, 0 ],
+        ]);
+    }
+
+    #[test]
+    fn test_stats_position_4() {
+        test_stats_helper("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1", vec![
+            [ 6, 0, 0, 0, 0, 0, 0, 0, 0 ],
+            [ 264, 87, 0, 6, 48, 10, 0, 0, 0 ],
+            [ 9467, 1021, 4, 0, 120, 38, 2, 0, 22 ],
+            [ 422333, 131393, 0, 7795, 60032, 15492, 19, 0, 5 ],
+        ]);
+    }
+
+    // Only the node counts are published for positions 5 and 6, so the other columns aren't checked for them
+    fn test_stats_nodes_helper(fen: &str, results: Vec<u64>) {
+        let chess_board = ChessBoard::new(Some(fen)).unwrap();
+        for (depth, result) in std::iter::zip(1.., results) {
+            assert_eq!(perft_stats(&chess_board, depth).nodes, result, "depth {}", depth);
+        }
+    }
+
+    #[test]
+    fn test_stats_position_5() {
+        test_stats_nodes_helper("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8", vec![ 44, 1486, 62379, 2103487 ]);
+    }
+
+    #[test]
+    fn test_stats_position_6() {
+        test_stats_nodes_helper("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10", vec![ 46, 2079, 89890, 3894594 ]);
+    }
+}
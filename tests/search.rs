@@ -0,0 +1,261 @@
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::time::{ Duration, Instant };
+
+use puhl_chess::*;
+
+mod tests {
+    use super::*;
+
+    fn search(fen: &str, limits: SearchLimits) -> SearchResult {
+        let chess_board = ChessBoard::new(Some(fen)).unwrap();
+        Searcher::new(Arc::new(AtomicBool::new(false))).search(&chess_board, &[], limits, |_| {})
+    }
+
+    fn depth(depth: u32) -> SearchLimits {
+        SearchLimits { depth: Some(depth), ..Default::default() }
+    }
+
+    #[test]
+    fn test_mate_in_one() {
+        let result = search("6k1/5ppp/8/8/8/8/8/R6K w - - 0 1", depth(2));
+        assert_eq!(result.best_move.unwrap().to_string(), "a1a8");
+        assert_eq!(result.score, Score::Mate(1));
+    }
+
+    fn search_with_params(fen: &str, limits: SearchLimits, params: SearchParams) -> SearchResult {
+        let chess_board = ChessBoard::new(Some(fen)).unwrap();
+        let mut searcher = Searcher::new(Arc::new(AtomicBool::new(false)));
+        searcher.set_params(params);
+        searcher.search(&chess_board, &[], limits, |_| {})
+    }
+
+    #[test]
+    fn test_mate_in_two() {
+        // A plain alpha-beta search finds the mate at the depth of the mate
+        let result = search_with_params("k7/8/2K5/8/8/8/8/7R w - - 0 1", depth(4), SearchParams::none());
+        assert_eq!(result.score, Score::Mate(2));
+        assert_eq!(result.pv.len(), 3);
+
+        // The reductions may need a deeper search to find the shortest mate
+        let result = search("k7/8/2K5/8/8/8/8/7R w - - 0 1", depth(6));
+        assert_eq!(result.score, Score::Mate(2));
+        assert_eq!(result.pv.len(), 3);
+    }
+
+    #[test]
+    fn test_getting_mated() {
+        let result = search("k7/8/1K6/8/8/8/8/7R b - - 0 1", depth(3));
+        assert_eq!(result.best_move.unwrap().to_string(), "a8b8");
+        assert_eq!(result.score, Score::Mate(-1));
+    }
+
+    #[test]
+    fn test_no_legal_moves() {
+        let result = search("R5k1/5ppp/8/8/8/8/8/7K b - - 0 1", depth(3));
+        assert!(result.best_move.is_none());
+    }
+
+    #[test]
+    fn test_captures_hanging_piece() {
+        let result = search("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1", depth(3));
+        assert_eq!(result.best_move.unwrap().to_string(), "d2d5");
+        assert!(matches!(result.score, Score::Centipawns(centipawns) if centipawns > 300));
+    }
+
+    #[test]
+    fn test_score_from_value() {
+        assert_eq!(Score::from_value(35), Score::Centipawns(35));
+        assert_eq!(Score::from_value(MATE_SCORE - 1), Score::Mate(1));
+        assert_eq!(Score::from_value(MATE_SCORE - 3), Score::Mate(2));
+        assert_eq!(Score::from_value(-MATE_SCORE + 2), Score::Mate(-1));
+        assert_eq!(Score::Mate(-1).to_string(), "mate -1");
+        assert_eq!(Score::Centipawns(35).to_string(), "cp 35");
+    }
+
+    #[test]
+    fn test_params() {
+        // Every feature alone still finds the best move, and together they search fewer nodes than plain alpha-beta
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let none = SearchParams::none();
+        let features = [
+            SearchParams { null_move: true, ..none },
+            SearchParams { late_move_reductions: true, ..none },
+            SearchParams { futility_pruning: true, ..none },
+            SearchParams { reverse_futility_pruning: true, ..none },
+            SearchParams { check_extensions: true, ..none },
+            SearchParams { aspiration_windows: true, ..none },
+        ];
+        for params in features {
+            let result = search_with_params("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1", depth(5), params);
+            assert_eq!(result.best_move.unwrap().to_string(), "d2d5");
+        }
+
+        let plain = search_with_params(fen, depth(5), none);
+        let pruned = search_with_params(fen, depth(5), SearchParams::default());
+        assert!(pruned.nodes < plain.nodes);
+    }
+
+    #[test]
+    fn test_shallow_lmr_min_depth() {
+        // Reductions at depths below 3 are clamped to zero instead of reducing into the quiescence search
+        let params = SearchParams { lmr_min_depth: 1, ..Default::default() };
+        let result = search_with_params("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", depth(4), params);
+        assert!(result.best_move.is_some());
+    }
+
+    #[test]
+    fn test_time_budget() {
+        let minute = Duration::from_secs(60);
+        assert_eq!(time_budget(minute, Duration::ZERO, None), minute / DEFAULT_MOVES_TO_GO);
+        assert_eq!(time_budget(minute, Duration::from_secs(4), Some(10)), Duration::from_secs(9));
+        // The overhead is kept back from the last move before the time control, and the budget never reaches zero
+        assert_eq!(time_budget(minute, Duration::ZERO, Some(1)), minute - MOVE_OVERHEAD);
+        assert_eq!(time_budget(Duration::from_millis(10), Duration::ZERO, Some(0)), Duration::from_millis(1));
+    }
+
+    #[test]
+    fn test_depth_limit() {
+        let chess_board = ChessBoard::new(None).unwrap();
+        let mut depths = vec![];
+        let result = Searcher::new(Arc::new(AtomicBool::new(false))).search(&chess_board, &[], depth(3), |info| {
+            assert!(!info.pv.is_empty());
+            depths.push(info.depth);
+        });
+        assert_eq!(depths, vec![1, 2, 3]);
+        assert_eq!(result.depth, 3);
+        assert!(chess_board.legal_moves().contains(&result.best_move.unwrap()));
+    }
+
+    #[test]
+    fn test_node_limit() {
+        let result = search(ChessBoard::INITIAL_POSITION_FEN, SearchLimits { nodes: Some(2000), ..Default::default() });
+        assert!(result.nodes <= 2000);
+        assert!(result.best_move.is_some());
+    }
+
+    #[test]
+    fn test_time_limit() {
+        let start = Instant::now();
+        let result = search(ChessBoard::INITIAL_POSITION_FEN, SearchLimits { time: Some(Duration::from_millis(100)), ..Default::default() });
+        assert!(start.elapsed() < Duration::from_secs(2));
+        assert!(result.best_move.is_some());
+    }
+
+    #[test]
+    fn test_stop_flag() {
+        let chess_board = ChessBoard::new(None).unwrap();
+        let result = Searcher::new(Arc::new(AtomicBool::new(true))).search(&chess_board, &[], SearchLimits::default(), |_| {});
+        assert!(result.nodes <= 1024);
+        assert!(result.best_move.is_some());
+    }
+
+    #[test]
+    fn test_repetition_is_draw() {
+        // White is a queen down, and moving the rook again repeats the position after the first move
+        let mut game = Game::new(Some("7k/8/8/5q2/8/8/8/R6K w - - 0 1")).unwrap();
+        for uci in ["a1a2", "h8g8", "a2a1", "g8h8"] {
+            game.make_move(game.chess_board().parse_move(uci).unwrap()).unwrap();
+        }
+        let result = Searcher::new(Arc::new(AtomicBool::new(false))).search(game.chess_board(), &game.history(), depth(3), |_| {});
+        assert_eq!(result.best_move.unwrap().to_string(), "a1a2");
+        assert_eq!(result.score, Score::Centipawns(0));
+    }
+
+    #[test]
+    fn test_variant() {
+        // Capturing on f7 explodes the king on e8 in Atomic
+        let chess_board = ChessBoard::with_variant(&Atomic, Some("rnbqkbnr/pppppppp/8/6N1/8/8/PPPPPPPP/RNBQKB1R w KQkq - 0 1")).unwrap();
+        let result = Searcher::new(Arc::new(AtomicBool::new(false))).search(&chess_board, &[], depth(2), |_| {});
+        assert_eq!(result.best_move.unwrap().to_string(), "g5f7");
+        assert_eq!(result.score, Score::Mate(1));
+    }
+
+    fn search_with_threads(fen: &str, limits: SearchLimits, threads: usize) -> SearchResult {
+        let chess_board = ChessBoard::new(Some(fen)).unwrap();
+        let mut searcher = Searcher::new(Arc::new(AtomicBool::new(false)));
+        searcher.set_threads(threads);
+        searcher.search(&chess_board, &[], limits, |_| {})
+    }
+
+    #[test]
+    fn test_single_thread_is_deterministic() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let first = search_with_threads(fen, depth(6), 1);
+        let second = search_with_threads(fen, depth(6), 1);
+        assert_eq!(first.nodes, second.nodes);
+        assert_eq!(first.pv, second.pv);
+        assert_eq!(first.score, second.score);
+
+        let limited = search_with_threads(fen, SearchLimits { nodes: Some(5000), ..Default::default() }, 1);
+        assert_eq!(limited.nodes, 5000);
+    }
+
+    #[test]
+    fn test_threads() {
+        let mut searcher = Searcher::new(Arc::new(AtomicBool::new(false)));
+        assert_eq!(searcher.threads(), 1);
+        searcher.set_threads(0);
+        assert_eq!(searcher.threads(), 1);
+
+        let result = search_with_threads("k7/8/2K5/8/8/8/8/7R w - - 0 1", depth(6), 4);
+        assert_eq!(result.score, Score::Mate(2));
+        assert_eq!(result.depth, 6);
+
+        // The helper threads stop with the main thread
+        let start = Instant::now();
+        let result = search_with_threads(ChessBoard::INITIAL_POSITION_FEN, SearchLimits { time: Some(Duration::from_millis(100)), ..Default::default() }, 3);
+        assert!(start.elapsed() < Duration::from_millis(1000));
+        assert!(result.best_move.is_some());
+
+        let stop = Arc::new(AtomicBool::new(true));
+        let mut searcher = Searcher::new(stop);
+        searcher.set_threads(4);
+        let result = searcher.search(&ChessBoard::new(None).unwrap(), &[], SearchLimits::default(), |_| {});
+        assert!(result.nodes <= 4 * 1024);
+    }
+
+    #[test]
+    fn test_multi_pv() {
+        let chess_board = ChessBoard::new(Some("6k1/5ppp/8/8/8/8/8/R6K w - - 0 1")).unwrap();
+        let mut searcher = Searcher::new(Arc::new(AtomicBool::new(false)));
+        assert_eq!(searcher.multi_pv(), 1);
+        searcher.set_multi_pv(3);
+        let mut infos = vec![];
+        let result = searcher.search(&chess_board, &[], depth(3), |info| infos.push(info.clone()));
+
+        // The mate comes first, the other lines have different first moves
+        assert_eq!(result.lines.len(), 3);
+        assert_eq!(result.lines[0].score, Score::Mate(1));
+        assert_eq!(result.lines[0].pv, result.pv);
+        assert_eq!(result.score, Score::Mate(1));
+        assert!(matches!(result.lines[1].score, Score::Centipawns(_)));
+        let mut first_moves: Vec<ChessMove> = result.lines.iter().map(|line| line.pv[0]).collect();
+        first_moves.sort_by_key(|chess_move| chess_move.to_string());
+        first_moves.dedup();
+        assert_eq!(first_moves.len(), 3);
+
+        // Every line of every iteration is reported, best first
+        assert_eq!(infos.len(), 9);
+        for (index, info) in infos.iter().enumerate() {
+            assert_eq!(info.depth, index as u32 / 3 + 1);
+            assert_eq!(info.multi_pv, index % 3 + 1);
+        }
+        assert_eq!(infos[6].pv, result.lines[0].pv);
+        assert_eq!(infos[8].score, result.lines[2].score);
+    }
+
+    #[test]
+    fn test_multi_pv_limited_by_legal_moves() {
+        // The king has only three moves
+        let chess_board = ChessBoard::new(Some("k7/8/8/8/8/8/8/2R4K b - - 0 1")).unwrap();
+        let mut searcher = Searcher::new(Arc::new(AtomicBool::new(false)));
+        searcher.set_multi_pv(5);
+        let result = searcher.search(&chess_board, &[], depth(3), |_| {});
+        assert_eq!(result.lines.len(), 3);
+
+        // A single line is the same as the best line
+        let result = search("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", depth(4));
+        assert_eq!(result.lines, [SearchLine { score: result.score, pv: result.pv.clone() }]);
+    }
+}
@@ -0,0 +1,67 @@
+use puhl_chess::*;
+
+mod tests {
+    use super::*;
+
+    fn see(fen: &str, uci: &str) -> i32 {
+        let chess_board = ChessBoard::new(Some(fen)).unwrap();
+        chess_board.see(chess_board.parse_move(uci).unwrap())
+    }
+
+    #[test]
+    fn test_undefended_capture() {
+        assert_eq!(see("1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1", "e1e5"), SEE_VALUES[PieceType::Pawn as usize]);
+    }
+
+    #[test]
+    fn test_defended_capture() {
+        // The knight takes a pawn and is taken by the knight, and taking back with the rook loses the rook to the bishop
+        assert_eq!(see("1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1", "d3e5"), -200);
+        // The pawn takes a knight and is taken by a pawn
+        assert_eq!(see("4k3/8/2p5/3n4/4P3/8/8/4K3 w - - 0 1", "e4d5"), 200);
+    }
+
+    #[test]
+    fn test_quiet_move() {
+        assert_eq!(see("4k3/8/8/3p4/8/1Q6/8/4K3 w - - 0 1", "b3c4"), -900);
+        assert_eq!(see("4k3/8/8/3p4/8/1Q6/8/4K3 w - - 0 1", "b3b4"), 0);
+        assert_eq!(see("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "e1g1"), 0);
+    }
+
+    #[test]
+    fn test_x_ray() {
+        // The rook behind the rook which captures first takes back
+        assert_eq!(see("4r1k1/8/8/4p3/8/8/4R3/4R1K1 w - - 0 1", "e2e5"), 100);
+        assert_eq!(see("4r1k1/4r3/8/4p3/8/8/4R3/6K1 w - - 0 1", "e2e5"), -400);
+    }
+
+    #[test]
+    fn test_king() {
+        // The king can't take back since the rook defends the pawn
+        assert_eq!(see("8/8/4k3/3p4/8/2N5/8/3RK3 w - - 0 1", "c3d5"), 100);
+        assert_eq!(see("8/8/4k3/3p4/8/2N5/8/4K3 w - - 0 1", "c3d5"), -200);
+    }
+
+    #[test]
+    fn test_special_moves() {
+        assert_eq!(see("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6"), 100);
+        assert_eq!(see("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7b8q"), 800);
+        assert_eq!(see("r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7b8q"), -100);
+        assert_eq!(see("r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7a8n"), 700);
+    }
+
+    #[test]
+    fn test_drop() {
+        let chess_board = ChessBoard::with_variant(&Crazyhouse, Some("4k3/8/8/3p4/8/8/8/4K3[Q] w - - 0 1")).unwrap();
+        assert_eq!(chess_board.see(chess_board.parse_move("Q@c4").unwrap()), -900);
+        assert_eq!(chess_board.see(chess_board.parse_move("Q@c3").unwrap()), 0);
+    }
+
+    #[test]
+    fn test_see_ge() {
+        let chess_board = ChessBoard::new(Some("1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1")).unwrap();
+        let chess_move = chess_board.parse_move("d3e5").unwrap();
+        assert!(chess_board.see_ge(chess_move, -200));
+        assert!(!chess_board.see_ge(chess_move, 0));
+    }
+}
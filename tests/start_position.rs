@@ -0,0 +1,78 @@
+use puhl_chess::*;
+
+mod tests {
+    use super::*;
+
+    fn square(name: &str) -> Index {
+        Index::from_algebraic(name).unwrap()
+    }
+
+    fn hash_of(fen: &str) -> u64 {
+        ChessBoard::new(Some(fen)).unwrap().hash()
+    }
+
+    #[test]
+    fn test_standard() {
+        let chess_board = StartPosition::new().build().unwrap();
+        assert_eq!(chess_board.hash(), ChessBoard::new(None).unwrap().hash());
+        assert_eq!(perft(&chess_board, 3), 8902);
+    }
+
+    #[test]
+    fn test_odds() {
+        let rook_odds = StartPosition::odds(Odds::Rook, PieceColor::White).build().unwrap();
+        assert_eq!(rook_odds.hash(), hash_of("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/1NBQKBNR w Kkq - 0 1"));
+        let knight_odds = StartPosition::odds(Odds::Knight, PieceColor::Black).build().unwrap();
+        assert_eq!(knight_odds.hash(), hash_of("r1bqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"));
+        let queen_odds = StartPosition::odds(Odds::Queen, PieceColor::White).build().unwrap();
+        assert_eq!(queen_odds.hash(), hash_of("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNB1KBNR w KQkq - 0 1"));
+
+        // The player giving pawn and move plays second
+        let pawn_and_move = StartPosition::odds(Odds::PawnAndMove, PieceColor::White).build().unwrap();
+        assert_eq!(pawn_and_move.hash(), hash_of("rnbqkbnr/pppppppp/8/8/8/8/PPPPP1PP/RNBQKBNR b KQkq - 0 1"));
+        let pawn_and_move = StartPosition::odds(Odds::PawnAndMove, PieceColor::Black).build().unwrap();
+        assert_eq!(pawn_and_move.hash(), hash_of("rnbqkbnr/ppppp1pp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"));
+    }
+
+    #[test]
+    fn test_castling_rights() {
+        let chess_board = StartPosition::new().remove(square("a1")).remove(square("h8")).build().unwrap();
+        assert_eq!(chess_board.hash(), hash_of("rnbqkbn1/pppppppp/8/8/8/8/PPPPPPPP/1NBQKBNR w Kq - 0 1"));
+        assert!(chess_board.parse_move("e1c1").is_none());
+
+        // A king which isn't on its square can't castle
+        let chess_board = StartPosition::empty()
+            .add(square("f1"), PieceType::King, PieceColor::White)
+            .add(square("h1"), PieceType::Rook, PieceColor::White)
+            .add(square("e8"), PieceType::King, PieceColor::Black)
+            .add(square("a8"), PieceType::Rook, PieceColor::Black)
+            .build().unwrap();
+        assert_eq!(chess_board.hash(), hash_of("r3k3/8/8/8/8/8/8/5K1R w q - 0 1"));
+    }
+
+    #[test]
+    fn test_added_pieces() {
+        let chess_board = StartPosition::new()
+            .remove(square("b8"))
+            .add(square("d4"), PieceType::Knight, PieceColor::Black)
+            .side_to_move(PieceColor::Black)
+            .build().unwrap();
+        assert_eq!(chess_board.info().player_turn, PieceColor::Black);
+        assert_eq!(chess_board.hash(), hash_of("r1bqkbnr/pppppppp/8/8/3n4/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1"));
+        assert_eq!(StartPosition::new().piece(square("e1")), Some((PieceType::King, PieceColor::White)));
+        assert_eq!(StartPosition::new().piece(square("e4")), None);
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(StartPosition::empty().build().is_none());
+        assert!(StartPosition::new().remove(square("e1")).build().is_none());
+        assert!(StartPosition::new().add(square("e4"), PieceType::King, PieceColor::White).build().is_none());
+        assert!(StartPosition::new().remove(square("a2")).add(square("a1"), PieceType::Pawn, PieceColor::White).build().is_none());
+
+        // The player who doesn't move can't be in check
+        let start_position = StartPosition::new().remove(square("e7")).add(square("e2"), PieceType::Queen, PieceColor::White);
+        assert!(start_position.clone().build().is_none());
+        assert!(start_position.side_to_move(PieceColor::Black).build().is_some());
+    }
+}
@@ -0,0 +1,139 @@
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::thread;
+
+use puhl_chess::*;
+
+mod tests {
+    use super::*;
+
+    fn chess_move(uci: &str) -> ChessMove {
+        ChessBoard::new(None).unwrap().parse_move(uci).unwrap()
+    }
+
+    #[test]
+    fn test_store_and_probe() {
+        let table = TranspositionTable::new(1);
+        assert!(table.probe(12345, 0).is_none());
+
+        table.store(12345, 0, 5, Bound::Lower, -120, Some(chess_move("g1f3")));
+        let entry = table.probe(12345, 0).unwrap();
+        assert_eq!(entry.depth, 5);
+        assert_eq!(entry.bound, Bound::Lower);
+        assert_eq!(entry.score, -120);
+        assert_eq!(entry.best_move, Some(chess_move("g1f3")));
+        assert_eq!(entry.age, table.age());
+        assert!(table.probe(12346, 0).is_none());
+    }
+
+    #[test]
+    fn test_moves() {
+        let table = TranspositionTable::new(1);
+        let promotion = ChessMove::new(Index::from_algebraic("b7").unwrap(), Index::from_algebraic("a8").unwrap(), Some(PieceType::Knight));
+        let drop = ChessMove::new_drop(PieceType::Knight, Index::from_algebraic("h1").unwrap());
+        for (hash, best_move) in [(1, Some(promotion)), (2, Some(drop)), (3, None)] {
+            table.store(hash, 0, 1, Bound::Exact, 0, best_move);
+            assert_eq!(table.probe(hash, 0).unwrap().best_move, best_move);
+        }
+    }
+
+    #[test]
+    fn test_keeps_best_move() {
+        let table = TranspositionTable::new(1);
+        table.store(7, 0, 3, Bound::Lower, 50, Some(chess_move("e2e4")));
+        table.store(7, 0, 4, Bound::Upper, 10, None);
+        assert_eq!(table.probe(7, 0).unwrap().best_move, Some(chess_move("e2e4")));
+    }
+
+    #[test]
+    fn test_mate_scores() {
+        let table = TranspositionTable::new(1);
+        // A mate in 3 plies from a position at ply 4 is a mate in 5 plies from a position at ply 2
+        table.store(99, 4, 6, Bound::Exact, MATE_SCORE - 7, None);
+        assert_eq!(table.probe(99, 4).unwrap().score, MATE_SCORE - 7);
+        assert_eq!(table.probe(99, 2).unwrap().score, MATE_SCORE - 5);
+
+        table.store(100, 4, 6, Bound::Exact, -MATE_SCORE + 6, None);
+        assert_eq!(table.probe(100, 1).unwrap().score, -MATE_SCORE + 3);
+    }
+
+    #[test]
+    fn test_replacement() {
+        // The smallest table has a single bucket
+        let table = TranspositionTable::new(0);
+        table.store(1, 0, 8, Bound::Exact, 1, None);
+        table.store(2, 0, 3, Bound::Exact, 2, None);
+        assert!(table.probe(1, 0).is_some());
+        assert!(table.probe(2, 0).is_some());
+
+        // A shallower entry replaces the always-replace slot and keeps the deep entry
+        table.store(3, 0, 2, Bound::Exact, 3, None);
+        assert!(table.probe(1, 0).is_some());
+        assert!(table.probe(2, 0).is_none());
+        assert!(table.probe(3, 0).is_some());
+
+        // A deeper entry replaces the depth-preferred slot
+        table.store(4, 0, 9, Bound::Exact, 4, None);
+        assert!(table.probe(1, 0).is_none());
+        assert!(table.probe(4, 0).is_some());
+
+        // Entries of earlier searches are replaced by shallower ones
+        table.new_search();
+        table.store(5, 0, 1, Bound::Exact, 5, None);
+        assert!(table.probe(4, 0).is_none());
+        assert!(table.probe(5, 0).is_some());
+    }
+
+    #[test]
+    fn test_resize_and_clear() {
+        let mut table = TranspositionTable::new(1);
+        assert!(table.size() <= 1024 * 1024);
+        table.store(42, 0, 1, Bound::Exact, 0, None);
+        assert!(table.hashfull() > 0);
+        table.clear();
+        assert!(table.probe(42, 0).is_none());
+        assert_eq!(table.hashfull(), 0);
+
+        table.store(42, 0, 1, Bound::Exact, 0, None);
+        table.resize(2);
+        assert!(table.size() > 1024 * 1024);
+        assert!(table.probe(42, 0).is_none());
+    }
+
+    #[test]
+    fn test_concurrent_use() {
+        let table = TranspositionTable::new(0);
+        thread::scope(|scope| {
+            for thread in 0..4u64 {
+                let table = &table;
+                scope.spawn(move || {
+                    for i in 0..10000u64 {
+                        let hash = i * 4 + thread;
+                        table.store(hash, 0, (hash % 100) as u8, Bound::Exact, (hash % 1000) as i32, None);
+                        // An entry torn by another thread never verifies
+                        if let Some(entry) = table.probe(hash, 0) {
+                            assert_eq!(entry.depth, (hash % 100) as u8);
+                            assert_eq!(entry.score, (hash % 1000) as i32);
+                        }
+                    }
+                });
+            }
+        });
+    }
+
+    #[test]
+    fn test_search_uses_table() {
+        let chess_board = ChessBoard::new(None).unwrap();
+        let mut searcher = Searcher::new(Arc::new(AtomicBool::new(false)));
+        let limits = SearchLimits { depth: Some(4), ..Default::default() };
+        let first = searcher.search(&chess_board, &[], limits, |_| {});
+        assert!(searcher.transposition_table().probe(chess_board.hash(), 0).is_some());
+
+        // Searching the same position again starts from the stored results
+        let second = searcher.search(&chess_board, &[], limits, |_| {});
+        assert!(second.nodes < first.nodes);
+
+        searcher.transposition_table_mut().resize(1);
+        assert!(searcher.transposition_table().probe(chess_board.hash(), 0).is_none());
+    }
+}
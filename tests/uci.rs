@@ -0,0 +1,113 @@
+mod common;
+
+use common::Engine;
+
+mod tests {
+    use super::*;
+
+    fn new_engine() -> Engine {
+        Engine::new(env!("CARGO_BIN_EXE_puhl-uci"))
+    }
+
+    #[test]
+    fn test_handshake() {
+        let mut engine = new_engine();
+        engine.send("uci");
+        let lines = engine.read_until("uciok");
+        assert!(lines[0].starts_with("id name puhl_chess"));
+        for option in ["Hash", "Threads", "MultiPV"] {
+            assert!(lines.iter().any(|line| line.starts_with(&format!("option name {} type spin", option))));
+        }
+        engine.send("isready");
+        assert_eq!(engine.read_until("readyok"), ["readyok"]);
+        engine.quit();
+    }
+
+    #[test]
+    fn test_go_depth() {
+        let mut engine = new_engine();
+        engine.send("position startpos moves e2e4 e7e5");
+        engine.send("go depth 3");
+        let lines = engine.read_until("bestmove");
+        assert_eq!(lines.len(), 4);
+        assert!(lines[2].starts_with("info depth 3 multipv 1 score cp "));
+        assert!(lines[2].contains(" nodes ") && lines[2].contains(" nps ") && lines[2].contains(" time ") && lines[2].contains(" pv "));
+
+        // The best move is the first move of the principal variation, and its reply is suggested for pondering
+        let pv: Vec<&str> = lines[2].split(" pv ").nth(1).unwrap().split(' ').collect();
+        assert_eq!(lines[3], format!("bestmove {} ponder {}", pv[0], pv[1]));
+        engine.quit();
+    }
+
+    #[test]
+    fn test_position_fen() {
+        let mut engine = new_engine();
+        engine.send("position fen 6k1/5ppp/8/8/8/8/8/R6K w - - 0 1");
+        engine.send("go mate 1");
+        let lines = engine.read_until("bestmove");
+        assert!(lines[0].contains("score mate 1"));
+        assert_eq!(lines.last().unwrap(), "bestmove a1a8");
+
+        // The moves are played from the position, there is no legal move after the mate
+        engine.send("position fen 6k1/5ppp/8/8/8/8/8/R6K w - - 0 1 moves a1a8");
+        engine.send("go depth 2");
+        assert_eq!(engine.read_until("bestmove"), ["bestmove 0000"]);
+
+        engine.send("position startpos moves e2e5");
+        engine.send("isready");
+        assert!(engine.read_until("readyok")[0].starts_with("info string Invalid position"));
+        engine.quit();
+    }
+
+    #[test]
+    fn test_multi_pv() {
+        let mut engine = new_engine();
+        engine.send("setoption name MultiPV value 3");
+        engine.send("setoption name Threads value 2");
+        engine.send("setoption name Hash value 1");
+        engine.send("position startpos");
+        engine.send("go depth 2");
+        let lines = engine.read_until("bestmove");
+        assert_eq!(lines.len(), 7);
+        for (index, line) in lines[3..6].iter().enumerate() {
+            assert!(line.starts_with(&format!("info depth 2 multipv {} ", index + 1)));
+        }
+        engine.quit();
+    }
+
+    #[test]
+    fn test_stop() {
+        let mut engine = new_engine();
+        engine.send("position startpos");
+        engine.send("go infinite");
+        // The engine keeps reading commands while it searches
+        engine.send("isready");
+        assert!(engine.read_until("readyok").iter().all(|line| !line.starts_with("bestmove")));
+        engine.send("stop");
+        engine.read_until("bestmove");
+
+        // A search with a depth limit waits for stop when it is infinite
+        engine.send("go infinite depth 1");
+        engine.send("isready");
+        let lines = engine.read_until("readyok");
+        assert!(lines.iter().all(|line| !line.starts_with("bestmove")));
+        engine.send("stop");
+        assert!(engine.read_until("bestmove").len() <= 2);
+        engine.quit();
+    }
+
+    #[test]
+    fn test_ponder() {
+        let mut engine = new_engine();
+        engine.send("position startpos moves e2e4");
+        engine.send("go ponder wtime 1000 btime 1000 depth 2");
+        engine.send("isready");
+        assert!(engine.read_until("readyok").iter().all(|line| !line.starts_with("bestmove")));
+        engine.send("ponderhit");
+        engine.read_until("bestmove");
+
+        engine.send("go wtime 100 btime 100 winc 0 binc 0");
+        engine.read_until("bestmove");
+        engine.quit();
+    }
+}
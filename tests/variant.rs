@@ -0,0 +1,347 @@
+// Perft results of the variant start positions match the ones published for python-chess and Fairy-Stockfish
+use puhl_chess::*;
+
+mod tests {
+    use super::*;
+
+    fn test_position_helper(variant: &'static dyn Variant, fen: Option<&str>, results: Vec<u64>) {
+        let chess_board = ChessBoard::with_variant(variant, fen).unwrap();
+        for (depth, result) in std::iter::zip(1.., results) {
+            let count = perft(&chess_board, depth);
+            assert_eq!(count, result);
+        }
+    }
+
+    // Counts the leaf nodes two plies deep by the rules of standard chess, skipping the first moves "ends_game" returns true for
+    fn standard_perft_2(fen: &str, ends_game: impl Fn(&ChessBoard, ChessMove) -> bool) -> u64 {
+        let chess_board = ChessBoard::new(Some(fen)).unwrap();
+        chess_board.legal_moves().into_iter()
+            .filter(|chess_move| !ends_game(&chess_board, *chess_move))
+            .map(|chess_move| perft(&chess_board.make_move(chess_move).unwrap().0, 1))
+            .sum()
+    }
+
+    #[test]
+    fn test_variant_by_name() {
+        for name in [ "chess", "kingofthehill", "3check", "racingkings", "horde", "crazyhouse", "bughouse", "atomic", "antichess" ] {
+            assert_eq!(variant_by_name(name).unwrap().name(), name);
+        }
+        assert!(variant_by_name("shatranj").is_none());
+    }
+
+    #[test]
+    fn test_square_moves() {
+        // The moves of every square match the legal moves, whether standard chess generates them per square or not
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        for variant in [ &Standard as &'static dyn Variant, &Crazyhouse ] {
+            let chess_board = ChessBoard::with_variant(variant, Some(fen)).unwrap();
+            let legal_moves = chess_board.legal_moves();
+            for index in 0..64 {
+                let square = chess_board.square(Rank::new(index / 8).unwrap(), File::new(index % 8).unwrap());
+                let moves = square.get_moves().unwrap_or_default();
+                for mv in &moves {
+                    assert!(legal_moves.iter().any(|chess_move| chess_move.src == mv.src && chess_move.dst == mv.dst));
+                }
+                let mut dsts: Vec<Index> = legal_moves.iter().filter(|chess_move| chess_move.src == square.as_index()).map(|chess_move| chess_move.dst).collect();
+                dsts.dedup();
+                assert_eq!(moves.len(), dsts.len());
+            }
+        }
+    }
+
+    #[test]
+    fn test_standard_start_position() {
+        test_position_helper(&Standard, None, vec![ 20, 400, 8902, 197281 ]);
+    }
+
+    #[test]
+    fn test_king_of_the_hill_start_position() {
+        test_position_helper(&KingOfTheHill, None, vec![ 20, 400, 8902, 197281 ]);
+    }
+
+    #[test]
+    fn test_king_of_the_hill() {
+        // Kd4 and Ke4 win the game, so the black king can't answer them
+        let fen = "4k3/8/8/8/8/3K4/8/8 w - - 0 1";
+        let hill = [ "d4", "e4", "d5", "e5" ];
+        let expected = standard_perft_2(fen, |_, chess_move| hill.contains(&chess_move.dst.to_string().as_str()));
+        test_position_helper(&KingOfTheHill, Some(fen), vec![ 8, expected ]);
+        assert_eq!(expected, 30);
+
+        let chess_board = ChessBoard::with_variant(&KingOfTheHill, Some(fen)).unwrap();
+        let mut game = Game::from_chess_board(chess_board);
+        game.make_move(game.chess_board().parse_move("d3e4").unwrap()).unwrap();
+        assert!(game.chess_board().legal_moves().is_empty());
+        assert_eq!(game.result(), GameResult { state: GameState::Win(PieceColor::White), termination: Some(Termination::VariantEnd) });
+
+        // A lone king can still reach the hill
+        let chess_board = ChessBoard::with_variant(&KingOfTheHill, Some("4k3/8/8/8/8/8/8/4K3 w - - 0 1")).unwrap();
+        assert_eq!(Game::from_chess_board(chess_board).result().state, GameState::Playing);
+    }
+
+    #[test]
+    fn test_three_check_start_position() {
+        test_position_helper(&ThreeCheck, None, vec![ 20, 400, 8902, 197281 ]);
+    }
+
+    #[test]
+    fn test_three_check() {
+        // White has given two checks, so Ra8+ wins the game
+        let fen = "4k3/8/8/8/8/8/8/R3K3 w - - 0 1";
+        let expected = standard_perft_2(fen, |chess_board, chess_move| chess_board.gives_check(chess_move));
+        test_position_helper(&ThreeCheck, Some(&format!("{} +2+0", fen)), vec![ 15, expected ]);
+        test_position_helper(&ThreeCheck, Some(&format!("{} +1+0", fen)), vec![ 15, perft(&ChessBoard::new(Some(fen)).unwrap(), 2) ]);
+
+        let chess_board = ChessBoard::with_variant(&ThreeCheck, Some(&format!("{} +2+0", fen))).unwrap();
+        let (chess_board, _) = chess_board.make_move(chess_board.parse_move("a1a8").unwrap()).unwrap();
+        assert_eq!(chess_board.inner.checks, [ 3, 0 ]);
+        assert_eq!(chess_board.info().game_state, GameState::Win(PieceColor::White));
+        assert_eq!(chess_board.hash(), ChessBoard::new(Some("R3k3/8/8/8/8/8/8/4K3 b - - 1 1 +3+0")).unwrap().hash());
+        assert_ne!(chess_board.hash(), ChessBoard::new(Some("R3k3/8/8/8/8/8/8/4K3 b - - 1 1")).unwrap().hash());
+
+        assert!(ChessBoard::with_variant(&ThreeCheck, Some(&format!("{} 2+0", fen))).is_none());
+        assert!(ChessBoard::with_variant(&ThreeCheck, Some(&format!("{} +2", fen))).is_none());
+    }
+
+    #[test]
+    fn test_racing_kings_start_position() {
+        test_position_helper(&RacingKings, None, vec![ 21, 421, 11264, 296242 ]);
+    }
+
+    #[test]
+    fn test_racing_kings_no_checks() {
+        let chess_board = ChessBoard::with_variant(&RacingKings, Some("8/8/8/8/8/2k5/8/R3K3 w - - 0 1")).unwrap();
+        assert!(chess_board.parse_move("a1b1").is_some());
+        assert!(chess_board.parse_move("a1c1").is_none());
+        assert!(chess_board.parse_move("a1a3").is_none());
+    }
+
+    #[test]
+    fn test_racing_kings_goal() {
+        // Black can't reach the eighth rank in one move
+        let chess_board = ChessBoard::with_variant(&RacingKings, Some("4K3/8/k7/8/8/8/8/8 b - - 0 1")).unwrap();
+        assert_eq!(chess_board.info().game_state, GameState::Win(PieceColor::White));
+
+        // Black can follow the white king, which draws the game
+        let chess_board = ChessBoard::with_variant(&RacingKings, Some("4K3/k7/8/8/8/8/8/8 b - - 0 1")).unwrap();
+        assert_eq!(chess_board.info().game_state, GameState::Playing);
+        let (chess_board, _) = chess_board.make_move(chess_board.parse_move("a7a8").unwrap()).unwrap();
+        assert_eq!(chess_board.info().game_state, GameState::Draw);
+
+        let chess_board = ChessBoard::with_variant(&RacingKings, Some("k7/8/8/8/8/8/8/4K3 w - - 0 1")).unwrap();
+        assert_eq!(chess_board.info().game_state, GameState::Win(PieceColor::Black));
+    }
+
+    #[test]
+    fn test_horde_start_position() {
+        test_position_helper(&Horde, None, vec![ 8, 128, 1274, 23310, 265223 ]);
+    }
+
+    #[test]
+    fn test_horde_first_rank_pawns() {
+        // The pawn on the first rank may move two squares, but it can't be captured en passant
+        let chess_board = ChessBoard::with_variant(&Horde, Some("4k3/8/8/8/8/1p6/8/P7 w - - 0 1")).unwrap();
+        assert_eq!(perft(&chess_board, 1), 2);
+        let (chess_board, _) = chess_board.make_move(chess_board.parse_move("a1a3").unwrap()).unwrap();
+        assert_eq!(chess_board.inner.en_passant_mask, 0);
+        assert!(chess_board.parse_move("b3a2").is_none());
+
+        let chess_board = ChessBoard::with_variant(&Horde, Some("4k3/8/8/8/8/8/P7/8 w - - 0 1")).unwrap();
+        assert_eq!(perft(&chess_board, 1), 2);
+    }
+
+    #[test]
+    fn test_horde_all_pawns_captured() {
+        let chess_board = ChessBoard::with_variant(&Horde, Some("4k3/8/8/8/8/8/1r6/P7 b - - 0 1")).unwrap();
+        let (chess_board, _) = chess_board.make_move(chess_board.parse_move("b2b1").unwrap()).unwrap();
+        assert_eq!(chess_board.info().game_state, GameState::Playing);
+        let chess_board = ChessBoard::with_variant(&Horde, Some("4k3/8/8/8/8/8/r7/P7 b - - 0 1")).unwrap();
+        let (chess_board, _) = chess_board.make_move(chess_board.parse_move("a2a1").unwrap()).unwrap();
+        assert_eq!(chess_board.info().game_state, GameState::Win(PieceColor::Black));
+        assert!(chess_board.legal_moves().is_empty());
+    }
+
+    #[test]
+    fn test_crazyhouse_start_position() {
+        test_position_helper(&Crazyhouse, None, vec![ 20, 400, 8902, 197281 ]);
+    }
+
+    #[test]
+    fn test_crazyhouse_all_drop_types() {
+        test_position_helper(&Crazyhouse, Some("2k5/8/8/8/8/8/8/4K3[QRBNPqrbnp] w - - 0 1"), vec![ 301, 75353 ]);
+    }
+
+    #[test]
+    fn test_crazyhouse_drops() {
+        let chess_board = ChessBoard::with_variant(&Crazyhouse, Some("4k3/8/8/8/8/8/8/4K3[N] w - - 0 1")).unwrap();
+        let chess_move = chess_board.parse_move("N@f3").unwrap();
+        assert_eq!(chess_move, ChessMove::new_drop(PieceType::Knight, Index::new(18).unwrap()));
+        assert_eq!(chess_move.to_string(), "N@f3");
+        let (chess_board, move_type) = chess_board.make_move(chess_move).unwrap();
+        assert_eq!(move_type, MoveType::Drop);
+        assert!(chess_board.pocket(PieceColor::White).is_empty());
+        let expected = ChessBoard::with_variant(&Crazyhouse, Some("4k3/8/8/8/8/5N2/8/4K3[] b - - 0 1")).unwrap();
+        assert_eq!(chess_board.hash(), expected.hash());
+        assert!(chess_board.parse_move("N@f4").is_none());
+
+        // A drop may block a check, but a pawn can't be dropped on the first rank
+        let chess_board = ChessBoard::with_variant(&Crazyhouse, Some("4k3/8/8/8/8/8/8/r3K3[N] w - - 0 1")).unwrap();
+        assert_eq!(perft(&chess_board, 1), 6);
+        let chess_board = ChessBoard::with_variant(&Crazyhouse, Some("4k3/8/8/8/8/8/8/r3K3[P] w - - 0 1")).unwrap();
+        assert_eq!(perft(&chess_board, 1), 3);
+
+        // A pocket can't hold more pieces of a type than a color has
+        let fen = format!("4k3/8/8/8/8/8/8/4K3[{}] w - - 0 1", "P".repeat(16));
+        assert!(ChessBoard::with_variant(&Crazyhouse, Some(&fen)).is_some());
+        let fen = format!("4k3/8/8/8/8/8/8/4K3[{}] w - - 0 1", "P".repeat(300));
+        assert!(ChessBoard::with_variant(&Crazyhouse, Some(&fen)).is_none());
+
+        // Drops aren't moves of standard chess
+        let chess_board = ChessBoard::new(Some("4k3/8/8/8/8/8/8/4K3[N] w - - 0 1")).unwrap();
+        assert!(chess_board.parse_move("N@f3").is_none());
+    }
+
+    #[test]
+    fn test_crazyhouse_captures() {
+        let chess_board = ChessBoard::with_variant(&Crazyhouse, Some("4k3/8/8/8/8/8/3q4/4K3 w - - 0 1")).unwrap();
+        let (chess_board, _) = chess_board.make_move(chess_board.parse_move("e1d2").unwrap()).unwrap();
+        assert_eq!(chess_board.pocket(PieceColor::White).count(PieceType::Queen), 1);
+
+        // A promoted piece goes to the pocket as a pawn
+        let chess_board = ChessBoard::with_variant(&Crazyhouse, Some("4k3/8/8/8/8/8/3q~4/4K3 w - - 0 1")).unwrap();
+        let (chess_board, _) = chess_board.make_move(chess_board.parse_move("e1d2").unwrap()).unwrap();
+        assert_eq!(chess_board.pocket(PieceColor::White).count(PieceType::Queen), 0);
+        assert_eq!(chess_board.pocket(PieceColor::White).count(PieceType::Pawn), 1);
+
+        let chess_board = ChessBoard::with_variant(&Crazyhouse, Some("4k3/3P4/8/8/8/8/8/4K3 w - - 0 1")).unwrap();
+        let (chess_board, _) = chess_board.make_move(chess_board.parse_move("d7d8q").unwrap()).unwrap();
+        let (chess_board, _) = chess_board.make_move(chess_board.parse_move("e8d8").unwrap()).unwrap();
+        assert_eq!(chess_board.pocket(PieceColor::Black).count(PieceType::Pawn), 1);
+    }
+
+    #[test]
+    fn test_crazyhouse_checkmate_by_drop() {
+        let chess_board = ChessBoard::with_variant(&Crazyhouse, Some("7k/6pp/8/8/8/8/8/K7[R] w - - 0 1")).unwrap();
+        assert!(chess_board.has_mating_material(PieceColor::White));
+        let (chess_board, _) = chess_board.make_move(chess_board.parse_move("R@e8").unwrap()).unwrap();
+        assert_eq!(chess_board.info().game_state, GameState::Win(PieceColor::White));
+    }
+
+    #[test]
+    fn test_bughouse() {
+        // The white player of board 0 captures a black queen, which goes to their partner, who plays black on board 1
+        let mut boards = BughouseBoards::from_fens([ "4k3/8/8/8/8/8/3q4/4K3 w - - 0 1", ChessBoard::INITIAL_POSITION_FEN ]).unwrap();
+        assert_eq!(boards.make_move(0, boards.board(0).parse_move("e1d2").unwrap()), Some(MoveType::Normal(Some(PieceType::Queen))));
+        assert!(boards.board(0).pocket(PieceColor::White).is_empty());
+        assert_eq!(boards.board(1).pocket(PieceColor::Black).count(PieceType::Queen), 1);
+
+        assert!(boards.make_move(1, boards.board(1).parse_move("e2e4").unwrap()).is_some());
+        assert!(boards.board(1).parse_move("Q@e3").is_some());
+        assert!(boards.make_move(1, boards.board(1).parse_move("Q@e6").unwrap()).is_some());
+        assert!(boards.board(1).pocket(PieceColor::Black).is_empty());
+        assert!(boards.make_move(1, ChessMove::new_drop(PieceType::Queen, Index::new(0).unwrap())).is_none());
+    }
+
+    // https://github.com/niklasf/python-chess/blob/master/examples/perft/atomic.perft
+    #[test]
+    fn test_atomic_start_position() {
+        test_position_helper(&Atomic, None, vec![ 20, 400, 8902, 197326 ]);
+    }
+
+    #[test]
+    fn test_atomic_positions() {
+        test_position_helper(&Atomic, Some("rn2kb1r/1pp1p2p/p2q1pp1/3P4/2P3b1/4PN2/PP3PPP/R2QKB1R b KQkq - 0 1"), vec![ 40, 1238, 45237 ]);
+        test_position_helper(&Atomic, Some("rn1qkb1r/p5pp/2p5/3p4/N3P3/5P2/PPP4P/R1BQK3 w Qkq - 0 1"), vec![ 28, 833, 23353 ]);
+    }
+
+    #[test]
+    fn test_atomic_castling() {
+        test_position_helper(&Atomic, Some("8/8/8/8/8/8/2k5/rR4KR w KQ - 0 1"), vec![ 18, 180, 4364, 61401 ]);
+        test_position_helper(&Atomic, Some("r3k1rR/5K2/8/8/8/8/8/8 b kq - 0 1"), vec![ 25, 282, 6753, 98729 ]);
+        test_position_helper(&Atomic, Some("Rr2k1rR/3K4/3p4/8/8/8/7P/8 w kq - 0 1"), vec![ 21, 465, 10631, 241478 ]);
+    }
+
+    #[test]
+    fn test_atomic_explosion() {
+        // Nxd4 explodes the knights, the rook, the queen and the bishop, but not the pawn
+        let chess_board = ChessBoard::with_variant(&Atomic, Some("4k3/8/8/2rqp3/3nb3/1N6/8/4K3 w - - 0 1")).unwrap();
+        let (chess_board, move_type) = chess_board.make_move(chess_board.parse_move("b3d4").unwrap()).unwrap();
+        assert_eq!(move_type, MoveType::Normal(Some(PieceType::Knight)));
+        assert_eq!(chess_board.hash(), ChessBoard::new(Some("4k3/8/8/4p3/8/8/8/4K3 b - - 0 1")).unwrap().hash());
+
+        // A capture next to the own king would explode it
+        let chess_board = ChessBoard::with_variant(&Atomic, Some("3R4/8/8/8/8/8/3r4/4K2k w - - 0 1")).unwrap();
+        assert!(chess_board.parse_move("d8d2").is_none());
+    }
+
+    #[test]
+    fn test_atomic_kings() {
+        // The king can't capture the queen which checks it
+        let chess_board = ChessBoard::with_variant(&Atomic, Some("4k3/8/8/8/8/8/4q3/4K3 w - - 0 1")).unwrap();
+        assert!(chess_board.parse_move("e1e2").is_none());
+        assert_eq!(chess_board.info().game_state, GameState::Win(PieceColor::Black));
+
+        // Kings next to each other are not in check
+        let chess_board = ChessBoard::with_variant(&Atomic, Some("8/8/8/8/8/8/4k3/r3K3 w - - 0 1")).unwrap();
+        let info = chess_board.info();
+        assert!(!info.is_current_player_in_check);
+        assert_eq!(info.game_state, GameState::Playing);
+
+        // Exploding the enemy king wins the game
+        let chess_board = ChessBoard::with_variant(&Atomic, Some("4k3/4r3/8/8/8/8/8/4R2K w - - 0 1")).unwrap();
+        let (chess_board, _) = chess_board.make_move(chess_board.parse_move("e1e7").unwrap()).unwrap();
+        assert_eq!(chess_board.info().game_state, GameState::Win(PieceColor::White));
+        assert!(chess_board.legal_moves().is_empty());
+    }
+
+    // https://github.com/niklasf/python-chess/blob/master/examples/perft/antichess.perft
+    #[test]
+    fn test_antichess_start_position() {
+        test_position_helper(&Antichess, None, vec![ 20, 400, 8067, 153299 ]);
+    }
+
+    #[test]
+    fn test_antichess_pawns() {
+        test_position_helper(&Antichess, Some("8/1p6/8/8/8/8/P7/8 w - - 0 1"), vec![ 2, 4, 4, 3, 1, 0 ]);
+    }
+
+    #[test]
+    fn test_antichess_captures() {
+        // The only legal moves are the captures, including the capture of the king, which isn't in check
+        let chess_board = ChessBoard::with_variant(&Antichess, Some("8/8/8/3k4/8/3R4/8/B7 w - - 0 1")).unwrap();
+        assert!(!chess_board.info().is_current_player_in_check);
+        let moves: Vec<String> = chess_board.legal_moves().iter().map(|chess_move| chess_move.to_string()).collect();
+        assert_eq!(moves, vec![ "d3d5" ]);
+
+        // A king may be captured by the king and moved next to the enemy king
+        let chess_board = ChessBoard::with_variant(&Antichess, Some("8/8/8/8/8/8/3k4/4K3 b - - 0 1")).unwrap();
+        assert!(chess_board.parse_move("d2e1").is_some());
+        assert!(chess_board.parse_move("d2d3").is_none());
+    }
+
+    #[test]
+    fn test_antichess_promotion() {
+        let chess_board = ChessBoard::with_variant(&Antichess, Some("8/P7/8/8/8/8/8/7k w - - 0 1")).unwrap();
+        assert_eq!(perft(&chess_board, 1), 5);
+        let (chess_board, move_type) = chess_board.make_move(chess_board.parse_move("a7a8k").unwrap()).unwrap();
+        assert_eq!(move_type, MoveType::Promotion);
+        assert_eq!(chess_board.hash(), ChessBoard::new(Some("K7/8/8/8/8/8/8/7k b - - 0 1")).unwrap().hash());
+
+        let chess_board = ChessBoard::new(Some("8/P7/8/8/8/8/8/k6K w - - 0 1")).unwrap();
+        assert!(chess_board.parse_move("a7a8k").is_none());
+    }
+
+    #[test]
+    fn test_antichess_end() {
+        // Capturing the last piece of the opponent makes them win
+        let chess_board = ChessBoard::with_variant(&Antichess, Some("8/8/8/8/8/8/1p6/R7 b - - 0 1")).unwrap();
+        assert_eq!(perft(&chess_board, 1), 5);
+        let (chess_board, _) = chess_board.make_move(chess_board.parse_move("b2a1q").unwrap()).unwrap();
+        assert_eq!(chess_board.info().game_state, GameState::Win(PieceColor::White));
+        assert!(!chess_board.has_mating_material(PieceColor::Black));
+
+        // A stalemated player wins
+        let chess_board = ChessBoard::with_variant(&Antichess, Some("8/8/8/8/8/p7/P7/8 w - - 0 1")).unwrap();
+        assert_eq!(chess_board.info().game_state, GameState::Win(PieceColor::White));
+    }
+}
@@ -0,0 +1,167 @@
+mod common;
+
+use common::Engine;
+
+mod tests {
+    use super::*;
+
+    // Starts the engine and waits for the features of version 2 of the protocol
+    fn new_engine() -> Engine {
+        let mut engine = Engine::new(env!("CARGO_BIN_EXE_puhl-xboard"));
+        engine.send("xboard");
+        engine.send("protover 2");
+        engine.read_until("feature");
+        engine
+    }
+
+    // Returns the lines up to the pong of a ping, after every command sent before has been handled
+    fn sync(engine: &mut Engine) -> Vec<String> {
+        engine.send("ping 7");
+        let mut lines = engine.read_until("pong 7");
+        lines.pop();
+        lines
+    }
+
+    #[test]
+    fn test_features() {
+        let mut engine = new_engine();
+        engine.send("protover 2");
+        let features = engine.read_until("feature").pop().unwrap();
+        for feature in ["ping=1", "setboard=1", "usermove=1", "done=1"] {
+            assert!(features.contains(feature));
+        }
+        assert!(features.contains("myname=\"puhl_chess"));
+        assert!(sync(&mut engine).is_empty());
+        engine.quit();
+    }
+
+    #[test]
+    fn test_plays_black() {
+        let mut engine = new_engine();
+        engine.send("new");
+        engine.send("sd 2");
+        engine.send("usermove e2e4");
+        let reply = engine.read_until("move").pop().unwrap();
+        let reply = reply.strip_prefix("move ").unwrap().to_string();
+
+        // The reply was played, so white can move again
+        engine.send(&format!("usermove {}", reply));
+        assert_eq!(sync(&mut engine), [format!("Illegal move: {}", reply)]);
+        engine.send("usermove d2d4");
+        engine.read_until("move");
+        engine.quit();
+    }
+
+    #[test]
+    fn test_force_and_go() {
+        let mut engine = new_engine();
+        engine.send("new");
+        engine.send("force");
+        engine.send("usermove e2e4");
+        engine.send("e7e5");
+        engine.send("usermove e2e5");
+        assert_eq!(sync(&mut engine), ["Illegal move: e2e5"]);
+
+        // The engine plays the side to move
+        engine.send("sd 1");
+        engine.send("go");
+        let reply = engine.read_until("move").pop().unwrap();
+        assert!(reply.starts_with("move "));
+        engine.quit();
+    }
+
+    #[test]
+    fn test_no_claim_for_user() {
+        // The move of the user repeats the start position a third time, the engine leaves the claim to the interface
+        let mut engine = new_engine();
+        engine.send("new");
+        engine.send("force");
+        for chess_move in ["g1f3", "g8f6", "f3g1", "f6g8", "g1f3", "g8f6", "f3g1", "f6g8"] {
+            engine.send(&format!("usermove {}", chess_move));
+        }
+        assert!(sync(&mut engine).is_empty());
+        engine.quit();
+    }
+
+    #[test]
+    fn test_undo_and_remove() {
+        let mut engine = new_engine();
+        engine.send("new");
+        engine.send("force");
+        for chess_move in ["e2e4", "e7e5", "g1f3"] {
+            engine.send(&format!("usermove {}", chess_move));
+        }
+        engine.send("undo");
+        engine.send("usermove b1c3");
+        engine.send("remove");
+        // Black is to move after e2e4 e7e5 b1c3 was taken back to e2e4
+        engine.send("usermove d2d4");
+        engine.send("usermove e7e5");
+        assert_eq!(sync(&mut engine), ["Illegal move: d2d4"]);
+        engine.quit();
+    }
+
+    #[test]
+    fn test_setboard_and_result() {
+        let mut engine = new_engine();
+        engine.send("setboard 6k1/5ppp/8/8/8/8/8/R6K w - - 0 1");
+        engine.send("sd 3");
+        engine.send("go");
+        let lines = engine.read_until("1-0");
+        assert_eq!(lines, ["move a1a8", "1-0 {White mates}"]);
+
+        engine.send("setboard not a position");
+        assert_eq!(sync(&mut engine), ["tellusererror Illegal position"]);
+
+        // The engine stops playing after the result
+        engine.send("new");
+        engine.send("result 1-0 {White resigns}");
+        engine.send("usermove e2e4");
+        assert!(sync(&mut engine).is_empty());
+        engine.quit();
+    }
+
+    #[test]
+    fn test_post() {
+        let mut engine = new_engine();
+        engine.send("setboard 6k1/5ppp/8/8/8/8/8/R6K w - - 0 1");
+        engine.send("sd 2");
+        engine.send("post");
+        engine.send("go");
+        let lines = engine.read_until("1-0");
+        // Depth, score with mates as 100000 plus the moves, time in centiseconds, nodes and principal variation
+        assert_eq!(lines.len(), 4);
+        let fields: Vec<&str> = lines[1].split(' ').collect();
+        assert_eq!(fields[0], "2");
+        assert_eq!(fields[1], "100001");
+        assert!(fields[2].parse::<u64>().is_ok() && fields[3].parse::<u64>().is_ok());
+        assert_eq!(fields[4], "a1a8");
+
+        engine.send("new");
+        engine.send("nopost");
+        engine.send("force");
+        engine.send("usermove e2e4");
+        engine.send("go");
+        assert_eq!(engine.read_until("move").len(), 1);
+        engine.quit();
+    }
+
+    #[test]
+    fn test_time_controls() {
+        let mut engine = new_engine();
+        engine.send("new");
+        engine.send("level 40 0:30 0");
+        engine.send("time 100");
+        engine.send("otim 100");
+        engine.send("usermove e2e4");
+        engine.read_until("move");
+
+        engine.send("st 0.05");
+        engine.send("usermove d2d4");
+        engine.read_until("move");
+
+        engine.send("level 0 0:30");
+        assert_eq!(sync(&mut engine), ["Error (invalid time control): level 0 0:30"]);
+        engine.quit();
+    }
+}
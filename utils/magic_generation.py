@@ -0,0 +1,74 @@
+""" Simple tool to find the magic numbers used for the sliding piece attack lookup in src/core/precompute_masks.rs """
+# https://www.chessprogramming.org/Looking_for_Magics
+import random
+
+MASK_64 = (1 << 64) - 1
+
+ROOK_DIRS = [(1, 0), (-1, 0), (0, 1), (0, -1)]
+BISHOP_DIRS = [(1, 1), (1, -1), (-1, 1), (-1, -1)]
+
+def square_index(rank, file):
+    return rank * 8 + file
+
+def on_board(rank, file):
+    return 0 <= rank < 8 and 0 <= file < 8
+
+# The squares which can block the piece, the last square of every ray is never relevant
+def relevant_mask(square, dirs):
+    rank, file = divmod(square, 8)
+    mask = 0
+    for dr, df in dirs:
+        r, f = rank + dr, file + df
+        while on_board(r + dr, f + df):
+            mask |= 1 << square_index(r, f)
+            r, f = r + dr, f + df
+    return mask
+
+def attacks(square, occupied, dirs):
+    rank, file = divmod(square, 8)
+    result = 0
+    for dr, df in dirs:
+        r, f = rank + dr, file + df
+        while on_board(r, f):
+            result |= 1 << square_index(r, f)
+            if occupied & (1 << square_index(r, f)):
+                break
+            r, f = r + dr, f + df
+    return result
+
+# Every subset of the mask, using the Carry-Rippler trick
+def subsets(mask):
+    subset = 0
+    while True:
+        yield subset
+        subset = (subset - mask) & mask
+        if subset == 0:
+            return
+
+def find_magic(square, dirs, rng):
+    mask = relevant_mask(square, dirs)
+    bits = bin(mask).count("1")
+    shift = 64 - bits
+    occupancies = list(subsets(mask))
+    references = [attacks(square, occupied, dirs) for occupied in occupancies]
+
+    while True:
+        magic = rng.getrandbits(64) & rng.getrandbits(64) & rng.getrandbits(64)
+        if bin((mask * magic) & 0xFF00000000000000).count("1") < 6:
+            continue
+        table = {}
+        for occupied, reference in zip(occupancies, references):
+            index = ((occupied * magic) & MASK_64) >> shift
+            if table.setdefault(index, reference) != reference:
+                break
+        else:
+            return magic
+
+if __name__ == "__main__":
+    rng = random.Random(0x5EED)
+    for name, dirs in [("BISHOP_MAGICS", BISHOP_DIRS), ("ROOK_MAGICS", ROOK_DIRS)]:
+        print(f"const {name}: BySquare<u64> = [")
+        for rank in range(8):
+            magics = [find_magic(square_index(rank, file), dirs, rng) for file in range(8)]
+            print("    " + ", ".join(f"0x{magic:016X}" for magic in magics) + ",")
+        print("];")